@@ -0,0 +1,101 @@
+use crate::{database::AuditEntry, metrics::Recorded, types::State};
+use chrono::Utc;
+use std::error::Error;
+use twilight_model::{
+    channel::{message::AllowedMentions, Message},
+    id::{
+        marker::{GuildMarker, UserMarker},
+        Id,
+    },
+};
+
+/// Start an audit entry for an action on a relayed message, the outcome is filled in by `record`.
+pub fn entry(
+    guild_id: Option<Id<GuildMarker>>,
+    actor: Option<Id<UserMarker>>,
+    action: &str,
+    message: Option<&Message>,
+) -> AuditEntry {
+    AuditEntry {
+        at: Utc::now().timestamp(),
+        guild_id: guild_id.map(Id::get),
+        actor: actor.map_or(0, Id::get),
+        action: action.to_owned(),
+        message_id: message.map(|message| message.id.get()),
+        upstream: message
+            .and_then(|message| message.embeds.first())
+            .and_then(|embed| embed.url.clone()),
+        error: None,
+    }
+}
+
+/// Store an action with its outcome and post it into the audit channel, if there is one.
+///
+/// Auditing never fails the action itself, problems are only logged.
+pub async fn record(
+    state: &State,
+    mut entry: AuditEntry,
+    result: &Result<(), Box<dyn Error + Send + Sync>>,
+) {
+    entry.error = result.as_ref().err().map(ToString::to_string);
+    if state.config.dry_run {
+        entry.action.push_str(" (dry run)");
+    }
+
+    let line = describe(&entry);
+    tracing::info!(
+        actor = entry.actor,
+        action = %entry.action,
+        message = ?entry.message_id,
+        failed = entry.error.is_some(),
+        "Audit"
+    );
+
+    if let Err(err) = state.database.write(|data| data.audit.push(entry)) {
+        tracing::warn!("Could not store an audit entry: {}", err);
+    }
+
+    let channel_id = match state.config.audit_channel {
+        Some(channel_id) => channel_id,
+        None => return,
+    };
+
+    // The actor is mentioned to link their profile, not to ping them
+    let no_pings = AllowedMentions::default();
+    let request = match state
+        .http
+        .create_message(channel_id)
+        .allowed_mentions(Some(&no_pings))
+        .content(&line)
+    {
+        Ok(request) => request,
+        Err(err) => {
+            tracing::warn!("Could not build the audit message: {}", err);
+            return;
+        }
+    };
+
+    if let Err(err) = request.exec().recorded().await {
+        tracing::warn!("Could not post into the audit channel: {}", err);
+    }
+}
+
+/// Render an entry as a single line.
+pub fn describe(entry: &AuditEntry) -> String {
+    let mut line = format!("<t:{}:f> <@{}> `{}`", entry.at, entry.actor, entry.action);
+
+    if let Some(message_id) = entry.message_id {
+        line.push_str(&format!(" on message {}", message_id));
+    }
+    if let Some(upstream) = &entry.upstream {
+        line.push_str(&format!(" (<{}>)", upstream));
+    }
+
+    match &entry.error {
+        Some(error) => line.push_str(&format!(", failed: {}", error)),
+        None => line.push_str(", succeeded"),
+    }
+
+    // Stay within the message content limit
+    line.chars().take(2000).collect()
+}
@@ -0,0 +1,244 @@
+//! Operate the bot from a terminal, without connecting to the gateway.
+//!
+//! It reads the same environment (or .env file) as the bot. The database is kept in memory by
+//! both, so stop the bot before running anything which writes to it.
+
+use bot::{
+    config::Config,
+    constants::API_DOCS_REPO,
+    database::{Database, GuildSettings},
+    github::{build_issues, fetch_commits, submit_issues, GithubCreateIssue},
+    github_auth::GitHubAuth,
+    guilds::{default_settings, saved_settings},
+    logging,
+    replay::{replay, ReplaySource, ReplayedPush},
+    skip::{SkipRules, SkippedCommit},
+    types::BotState,
+};
+use clap::{Parser, Subcommand};
+use dotenv::dotenv;
+use std::{env, error::Error, sync::Arc};
+use twilight_http::Client as HttpClient;
+use twilight_model::id::{
+    marker::{GuildMarker, MessageMarker},
+    Id,
+};
+
+#[derive(Parser)]
+#[clap(
+    name = "apidocs-cli",
+    about = "Operate the API docs bot without the gateway"
+)]
+struct Cli {
+    /// Use the settings of this server, the defaults are used without it
+    #[clap(long, global = true)]
+    guild: Option<Id<GuildMarker>>,
+    #[clap(subcommand)]
+    command: CliCommand,
+}
+
+#[derive(Subcommand)]
+enum CliCommand {
+    /// Create the issues of a commit or compare url, like the issue button does
+    ///
+    /// The issues are not linked to any relayed message.
+    CreateIssues {
+        url: String,
+        /// Only show which issues would be created, always the case with `DRY_RUN=true`
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Print the issue payloads of a commit or compare url as JSON
+    Render { url: String },
+    /// Relay pushes the webhook posted into the relay channel again
+    Replay {
+        /// The id of the (first) message
+        message: Id<MessageMarker>,
+        /// The id of the last message, to replay a range
+        until: Option<Id<MessageMarker>>,
+        /// Only show what would be relayed, always the case with `DRY_RUN=true`
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Relay the commits between two SHAs as one push
+    Backfill {
+        /// The last commit which was relayed
+        base: String,
+        /// The last commit to relay
+        head: String,
+        #[clap(long, default_value = API_DOCS_REPO)]
+        repo: String,
+        #[clap(long, default_value = "master")]
+        branch: String,
+        /// Only show what would be relayed, always the case with `DRY_RUN=true`
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Work with the database
+    Db {
+        #[clap(subcommand)]
+        command: DbCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum DbCommand {
+    /// Print the database, or one part of it, as JSON
+    Inspect {
+        /// Like `jobs`, `audit` or `created_issues`
+        section: Option<String>,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+    // Load the .env file and just ignore any errors
+    dotenv().ok();
+    let _log_guard = logging::init()?;
+
+    let cli = Cli::parse();
+    let config = Config::from_env()?;
+    let github = Arc::new(GitHubAuth::from_config(&config)?);
+
+    match cli.command {
+        CliCommand::CreateIssues { url, dry_run } => {
+            let dry_run = dry_run || config.dry_run;
+            let settings = offline_settings(&config, cli.guild)?;
+            let (issues, skipped) = issues_of(&github, &url, &config.skip_rules, &settings).await?;
+            for commit in &skipped {
+                println!("Skipped {}", commit);
+            }
+
+            if dry_run {
+                for issue in &issues {
+                    println!("Would create {}", issue.title);
+                }
+            } else if settings.issue_repo.is_empty() {
+                return Err(
+                    "The guild has no issue repo, choose one with `/apidocs settings`".into(),
+                );
+            } else {
+                for issue in submit_issues(&github, &settings.issue_repo, issues).await? {
+                    println!("Created #{} {}", issue.number, issue.html_url);
+                }
+            }
+        }
+        CliCommand::Render { url } => {
+            let settings = offline_settings(&config, cli.guild)?;
+            let (issues, skipped) = issues_of(&github, &url, &config.skip_rules, &settings).await?;
+            // Only the payloads go to stdout, so they can be piped into other tools
+            for commit in &skipped {
+                eprintln!("Skipped {}", commit);
+            }
+
+            println!("{}", serde_json::to_string_pretty(&issues)?);
+        }
+        CliCommand::Db {
+            command: DbCommand::Inspect { section },
+        } => {
+            let database = Database::open(config.database_path)?;
+            let data = database.read(|data| serde_json::to_value(data))?;
+
+            let data = match section {
+                Some(section) => data
+                    .get(&section)
+                    .cloned()
+                    .ok_or_else(|| format!("The database has no `{}`", section))?,
+                None => data,
+            };
+
+            println!("{}", serde_json::to_string_pretty(&data)?);
+        }
+        CliCommand::Replay {
+            message,
+            until,
+            dry_run,
+        } => {
+            let source = ReplaySource::Messages {
+                first: message,
+                last: until.unwrap_or(message),
+            };
+
+            let dry_run = dry_run || config.dry_run;
+            let settings = offline_settings(&config, cli.guild)?;
+            let state = offline_state(config, Arc::clone(&github))?;
+            print_replay(&replay(&state, &settings, &source, dry_run).await?, dry_run);
+        }
+        CliCommand::Backfill {
+            base,
+            head,
+            repo,
+            branch,
+            dry_run,
+        } => {
+            let source = ReplaySource::Commits {
+                repo,
+                branch,
+                base,
+                head,
+            };
+
+            let dry_run = dry_run || config.dry_run;
+            let settings = offline_settings(&config, cli.guild)?;
+            let state = offline_state(config, Arc::clone(&github))?;
+            print_replay(&replay(&state, &settings, &source, dry_run).await?, dry_run);
+        }
+    }
+
+    Ok(())
+}
+
+/// The state of the bot without a gateway connection.
+fn offline_state(
+    config: Config,
+    github: Arc<GitHubAuth>,
+) -> Result<Arc<BotState>, Box<dyn Error + Send + Sync>> {
+    let http = Arc::new(HttpClient::new(env::var("DISCORD_TOKEN")?));
+    let database = Database::open(config.database_path.clone())?;
+
+    Ok(Arc::new(BotState::new(
+        http, None, config, database, github,
+    )))
+}
+
+/// The settings of a guild for commands which need no Discord connection.
+fn offline_settings(
+    config: &Config,
+    guild_id: Option<Id<GuildMarker>>,
+) -> Result<GuildSettings, Box<dyn Error + Send + Sync>> {
+    let guild_id = match guild_id {
+        Some(guild_id) => guild_id.get(),
+        None => return Ok(default_settings(config)),
+    };
+    let database = Database::open(config.database_path.clone())?;
+
+    Ok(database.read(|data| saved_settings(data, config, guild_id)))
+}
+
+/// The issues the skip rules leave of a commit or compare url, and what they skipped.
+async fn issues_of(
+    github: &GitHubAuth,
+    url: &str,
+    rules: &SkipRules,
+    settings: &GuildSettings,
+) -> Result<(Vec<GithubCreateIssue>, Vec<SkippedCommit>), Box<dyn Error + Send + Sync>> {
+    let (commits, skipped) = rules.partition(fetch_commits(github, url).await?);
+
+    Ok((build_issues(commits, &settings.template), skipped))
+}
+
+fn print_replay(pushes: &[ReplayedPush], dry_run: bool) {
+    for push in pushes {
+        println!("{}", push);
+    }
+
+    if dry_run {
+        println!("{} pushes found, nothing was relayed.", pushes.len());
+    } else {
+        println!(
+            "{} of {} pushes relayed.",
+            pushes.iter().filter(|push| push.relayed).count(),
+            pushes.len()
+        );
+    }
+}
@@ -0,0 +1,444 @@
+use crate::types::github::File;
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A field of an object structure table.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FieldChange {
+    /// The heading above the table, like `Channel Structure`.
+    pub table: String,
+    pub field: String,
+    /// The documented type, for removed fields the type before the removal.
+    pub kind: String,
+    /// The type before the change, for fields whose type changed.
+    pub old_kind: Option<String>,
+    pub description: String,
+}
+
+/// What a docs change means for the library, read from the Markdown diff.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangeSummary {
+    pub new_endpoints: Vec<String>,
+    pub removed_endpoints: Vec<String>,
+    pub new_fields: Vec<FieldChange>,
+    pub removed_fields: Vec<FieldChange>,
+    /// Fields which still exist but got a different type.
+    pub changed_types: Vec<FieldChange>,
+    pub deprecations: Vec<String>,
+}
+
+/// A table row as it appears on one side of the diff.
+struct Row {
+    field: String,
+    kind: String,
+    description: String,
+    deprecated: bool,
+}
+
+impl Row {
+    fn change(&self, table: &str, old_kind: Option<String>) -> FieldChange {
+        FieldChange {
+            table: table.to_owned(),
+            field: self.field.clone(),
+            kind: self.kind.clone(),
+            old_kind,
+            description: self.description.clone(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Side {
+    endpoints: BTreeSet<String>,
+    rows: BTreeMap<(String, String), Row>,
+    deprecations: BTreeSet<String>,
+}
+
+/// The patterns of the Discord docs, compiled once per analysis.
+struct Patterns {
+    /// `## Get Channel % GET /channels/{channel.id#DOCS_RESOURCES_CHANNEL/channel-object}`
+    old_route: Regex,
+    /// `<Route method="GET">/channels/[{channel.id}](#DOCS_RESOURCES_CHANNEL/channel-object)</Route>`
+    route: Regex,
+    link: Regex,
+    anchor: Regex,
+}
+
+impl Patterns {
+    fn new() -> Self {
+        Self {
+            old_route: Regex::new(r"^#{2,}\s*(.+?)\s*%\s*([A-Z]+)\s+(\S+)").unwrap(),
+            route: Regex::new(r#"<Route\s+method="([A-Z]+)"\s*>(.+?)</Route>"#).unwrap(),
+            link: Regex::new(r"\[([^\]]*)\]\([^)]*\)").unwrap(),
+            anchor: Regex::new(r"\{([^#}]*)#[^}]*\}").unwrap(),
+        }
+    }
+
+    /// Turn a documented path into the plain path, dropping links and anchors.
+    fn clean_path(&self, path: &str) -> String {
+        let path = self.link.replace_all(path, "$1");
+        let path = self.anchor.replace_all(&path, "{$1}");
+
+        path.replace('\\', "")
+    }
+}
+
+impl ChangeSummary {
+    /// Analyze the patches of the Markdown files of a commit.
+    pub fn analyze(files: &[File]) -> Self {
+        let patterns = Patterns::new();
+        let mut removed = Side::default();
+        let mut added = Side::default();
+
+        for file in files
+            .iter()
+            .filter(|file| file.filename.ends_with(".md") || file.filename.ends_with(".mdx"))
+        {
+            scan_patch(&patterns, &file.patch, &mut removed, &mut added);
+        }
+
+        let mut summary = Self {
+            new_endpoints: added
+                .endpoints
+                .difference(&removed.endpoints)
+                .cloned()
+                .collect(),
+            removed_endpoints: removed
+                .endpoints
+                .difference(&added.endpoints)
+                .cloned()
+                .collect(),
+            // A deprecation which was only moved around is not new
+            deprecations: added
+                .deprecations
+                .difference(&removed.deprecations)
+                .cloned()
+                .collect(),
+            ..Default::default()
+        };
+
+        for ((table, field), row) in &added.rows {
+            match removed.rows.get(&(table.clone(), field.clone())) {
+                None => summary.new_fields.push(row.change(table, None)),
+                Some(old) if old.kind != row.kind => summary
+                    .changed_types
+                    .push(row.change(table, Some(old.kind.clone()))),
+                Some(_) => {}
+            }
+        }
+
+        summary.removed_fields = removed
+            .rows
+            .iter()
+            .filter(|(key, _)| !added.rows.contains_key(*key))
+            .map(|((table, _), row)| row.change(table, None))
+            .collect();
+
+        summary
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Removing anything or changing a type breaks users of the library.
+    pub fn is_breaking(&self) -> bool {
+        !self.removed_endpoints.is_empty()
+            || !self.removed_fields.is_empty()
+            || !self.changed_types.is_empty()
+    }
+
+    /// The labels for an issue about this change.
+    pub fn labels(&self) -> Vec<&'static str> {
+        let mut labels = vec![];
+
+        if !self.new_endpoints.is_empty() {
+            labels.push("new-endpoint");
+        }
+        if !self.new_fields.is_empty() {
+            labels.push("new-field");
+        }
+        if !self.deprecations.is_empty() {
+            labels.push("deprecation");
+        }
+        if self.is_breaking() {
+            labels.push("breaking");
+        }
+
+        labels
+    }
+
+    /// The changes grouped by their kind, kinds without changes are left out.
+    pub fn sections(&self) -> Vec<(&'static str, Vec<String>)> {
+        let fields = |changes: &[FieldChange]| -> Vec<String> {
+            changes
+                .iter()
+                .map(|change| format!("`{}` in {}", change.field, change.table))
+                .collect()
+        };
+
+        vec![
+            ("New endpoints", self.new_endpoints.clone()),
+            ("Removed endpoints", self.removed_endpoints.clone()),
+            ("New fields", fields(&self.new_fields)),
+            ("Removed fields", fields(&self.removed_fields)),
+            ("Changed types", fields(&self.changed_types)),
+            ("Deprecations", self.deprecations.clone()),
+        ]
+        .into_iter()
+        .filter(|(_, entries)| !entries.is_empty())
+        .collect()
+    }
+
+    /// Render the summary as a Markdown section for the issue body.
+    pub fn to_markdown(&self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let mut lines = vec!["### Detected changes".to_owned()];
+        for (title, entries) in self.sections() {
+            lines.push(format!("**{}**", title));
+            lines.extend(entries.into_iter().map(|entry| format!("- {}", entry)));
+        }
+
+        Some(lines.join("\n"))
+    }
+}
+
+fn scan_patch(patterns: &Patterns, patch: &str, removed: &mut Side, added: &mut Side) {
+    // The heading the current lines belong to, GitHub puts the closest one into the hunk header
+    let mut heading = String::new();
+
+    for line in patch.lines() {
+        if let Some(header) = line.strip_prefix("@@") {
+            heading = header
+                .split_once("@@")
+                .map(|(_, context)| context.trim_start_matches(['#', ' ']))
+                .unwrap_or_default()
+                .to_owned();
+            continue;
+        }
+
+        let (side, text) = if let Some(text) = line.strip_prefix('+') {
+            (Some(&mut *added), text)
+        } else if let Some(text) = line.strip_prefix('-') {
+            (Some(&mut *removed), text)
+        } else {
+            (None, line.get(1..).unwrap_or_default())
+        };
+        let text = text.trim();
+
+        if text.starts_with('#') {
+            if let Some(captures) = patterns.old_route.captures(text) {
+                if let Some(side) = side {
+                    side.endpoints.insert(format!(
+                        "{} (`{} {}`)",
+                        &captures[1],
+                        &captures[2],
+                        patterns.clean_path(&captures[3])
+                    ));
+                }
+                heading = captures[1].to_owned();
+            } else {
+                heading = text.trim_start_matches('#').trim().to_owned();
+            }
+            continue;
+        }
+
+        let side = match side {
+            Some(side) => side,
+            None => continue,
+        };
+
+        if let Some(captures) = patterns.route.captures(text) {
+            side.endpoints.insert(format!(
+                "{} (`{} {}`)",
+                heading,
+                &captures[1],
+                patterns.clean_path(&captures[2])
+            ));
+        } else if let Some(row) = parse_row(text) {
+            if row.deprecated {
+                side.deprecations
+                    .insert(format!("{} in {}", row.field, heading));
+            }
+            side.rows.insert((heading.clone(), row.field.clone()), row);
+        } else if text.to_lowercase().contains("deprecated") {
+            side.deprecations.insert(heading.clone());
+        }
+    }
+}
+
+/// Parse a row of an object structure table, skipping headers and separators.
+fn parse_row(text: &str) -> Option<Row> {
+    let cells: Vec<_> = text
+        .strip_prefix('|')?
+        .split('|')
+        .map(|cell| cell.trim())
+        .collect();
+
+    let field = cells.first()?.replace(['\\', '*'], "");
+    let kind = cells.get(1)?.to_string();
+    let description = cells
+        .get(2)
+        .map_or_else(String::new, |cell| cell.to_string());
+
+    if field.is_empty()
+        || field.eq_ignore_ascii_case("field")
+        || field.eq_ignore_ascii_case("name")
+        || field.chars().all(|c| c == '-' || c == ':')
+    {
+        return None;
+    }
+
+    Some(Row {
+        field: field.trim().to_owned(),
+        kind,
+        description,
+        deprecated: text.to_lowercase().contains("deprecated"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn analyze(filename: &str, patch: &str) -> ChangeSummary {
+        ChangeSummary::analyze(&[File {
+            filename: filename.into(),
+            patch: patch.into(),
+            ..Default::default()
+        }])
+    }
+
+    #[test]
+    fn new_field_in_structure_table() {
+        let summary = analyze(
+            "docs/resources/Channel.md",
+            "@@ -30,6 +30,7 @@ Represents a guild or DM channel within Discord.
+ ###### Channel Structure
+ 
+ | Field | Type | Description |
+ |-------|------|-------------|
+ | id | snowflake | the id of this channel |
++| default_forum_layout? | integer | the [default forum layout view](#DOCS_RESOURCES_CHANNEL/forum-layout-types) used to display posts in `GUILD_FORUM` channels |
+ | type | integer | the [type of channel](#DOCS_RESOURCES_CHANNEL/channel-object-channel-types) |",
+        );
+
+        assert_eq!(summary.new_fields.len(), 1);
+        assert_eq!(summary.new_fields[0].table, "Channel Structure");
+        assert_eq!(summary.new_fields[0].field, "default_forum_layout?");
+        assert_eq!(summary.new_fields[0].kind, "integer");
+        assert_eq!(summary.labels(), vec!["new-field"]);
+        assert!(!summary.is_breaking());
+    }
+
+    #[test]
+    fn new_endpoint_in_the_old_route_format() {
+        let summary = analyze(
+            "docs/resources/Guild.md",
+            "@@ -1020,3 +1020,9 @@ Modify the guild's Welcome Screen.
+ | description? | ?string | the server description to show in the welcome screen |
++
++## Get Guild Onboarding % GET /guilds/{guild.id#DOCS_RESOURCES_GUILD/guild-object}/onboarding
++
++Returns the [Onboarding](#DOCS_RESOURCES_GUILD/guild-onboarding-object) object for the guild.",
+        );
+
+        assert_eq!(
+            summary.new_endpoints,
+            vec!["Get Guild Onboarding (`GET /guilds/{guild.id}/onboarding`)"]
+        );
+        assert_eq!(summary.labels(), vec!["new-endpoint"]);
+    }
+
+    #[test]
+    fn new_endpoint_in_the_route_component() {
+        let summary = analyze(
+            "docs/monetization/Entitlements.mdx",
+            "@@ -60,3 +60,7 @@ Entitlements in Discord represent that a user or guild has access to a premium offering in your application.
++## List Entitlements
++
++<Route method=\"GET\">/applications/[{application.id}](#DOCS_RESOURCES_APPLICATION/application-object)/entitlements</Route>
++",
+        );
+
+        assert_eq!(
+            summary.new_endpoints,
+            vec!["List Entitlements (`GET /applications/{application.id}/entitlements`)"]
+        );
+    }
+
+    #[test]
+    fn changed_and_removed_fields_are_breaking() {
+        let summary = analyze(
+            "docs/resources/Guild.md",
+            "@@ -60,9 +60,8 @@ Guilds in Discord represent an isolated collection of users and channels.
+ ###### Guild Structure
+ 
+-| permissions? \\* | integer | total permissions for [the user](#DOCS_RESOURCES_USER/get-current-user-guilds) in the guild |
++| permissions? \\* | string | total permissions for [the user](#DOCS_RESOURCES_USER/get-current-user-guilds) in the guild |
+-| max_presences? | ?integer | the maximum number of presences for the guild |
+ | max_members? | integer | the maximum number of members for the guild |",
+        );
+
+        assert_eq!(summary.changed_types.len(), 1);
+        assert_eq!(summary.changed_types[0].field, "permissions?");
+        assert_eq!(summary.changed_types[0].kind, "string");
+        assert_eq!(
+            summary.changed_types[0].old_kind.as_deref(),
+            Some("integer")
+        );
+        assert_eq!(summary.removed_fields.len(), 1);
+        assert_eq!(summary.removed_fields[0].field, "max_presences?");
+        assert!(summary.new_fields.is_empty());
+        assert!(summary.is_breaking());
+        assert_eq!(summary.labels(), vec!["breaking"]);
+    }
+
+    #[test]
+    fn deprecated_fields_are_listed_once() {
+        let summary = analyze(
+            "docs/resources/Guild.md",
+            "@@ -70,6 +70,6 @@ Guilds in Discord represent an isolated collection of users and channels.
+ ###### Guild Structure
+ 
+-| region? \\*\\* | ?string | [voice region](#DOCS_RESOURCES_VOICE/voice-region-object) id for the guild |
++| region? \\*\\* | ?string | [voice region](#DOCS_RESOURCES_VOICE/voice-region-object) id for the guild (deprecated) |
+ | afk_channel_id | ?snowflake | id of afk channel |",
+        );
+
+        assert_eq!(summary.deprecations, vec!["region? in Guild Structure"]);
+        assert!(summary.new_fields.is_empty());
+        assert!(summary.changed_types.is_empty());
+        assert_eq!(summary.labels(), vec!["deprecation"]);
+    }
+
+    #[test]
+    fn only_markdown_files_are_read() {
+        let summary = analyze(
+            "package.json",
+            "@@ -1,3 +1,3 @@
++| field | type | not a docs table |",
+        );
+
+        assert!(summary.is_empty());
+        assert_eq!(summary.to_markdown(), None);
+    }
+
+    #[test]
+    fn markdown_lists_the_changes() {
+        let summary = ChangeSummary {
+            new_endpoints: vec![
+                "List Entitlements (`GET /applications/{application.id}/entitlements`)".into(),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            summary.to_markdown().unwrap(),
+            "### Detected changes\n**New endpoints**\n- List Entitlements (`GET /applications/{application.id}/entitlements`)"
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{
+    routes::{load_routes, Route, RoutesError},
+    skip::{load_skip_rules, SkipRules, SkipRulesError},
+};
+use std::{
+    env,
+    fmt::{Display, Formatter, Result as FmtResult},
+    net::AddrParseError,
+    net::SocketAddr,
+    num::ParseIntError,
+    path::PathBuf,
+    time::Duration,
+};
+use twilight_model::id::{marker::ChannelMarker, Id};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("The environment variable {0} is not a valid number: {1}")]
+    InvalidNumber(&'static str, ParseIntError),
+    #[error("The environment variable {0} is not a valid address: {1}")]
+    InvalidAddress(&'static str, AddrParseError),
+    #[error("The environment variable {0} needs to be set")]
+    Missing(&'static str),
+    #[error("`{0}` is not a valid branch to watch, use `owner/repo:branch`")]
+    InvalidWatchedBranch(String),
+    #[error("`{0}` is not a valid issue repo, use `owner/repo`")]
+    InvalidIssueRepo(String),
+    #[error("The routes could not be loaded: {0}")]
+    Routes(#[from] RoutesError),
+    #[error("The skip rules could not be loaded: {0}")]
+    SkipRules(#[from] SkipRulesError),
+}
+
+/// A branch of a GitHub repo which is polled for new commits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchedBranch {
+    /// The repo in the `owner/repo` format.
+    pub repo: String,
+    pub branch: String,
+}
+
+impl Display for WatchedBranch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}:{}", self.repo, self.branch)
+    }
+}
+
+/// The GitHub App the bot authenticates as.
+#[derive(Debug, Clone)]
+pub struct GitHubAppConfig {
+    pub app_id: u64,
+    /// The PEM file of the private key of the app.
+    pub private_key_path: PathBuf,
+    /// The installation used for repos the app is not installed on, like the upstream repos.
+    pub installation_id: u64,
+}
+
+/// Settings which can be changed through the environment (or the .env file).
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Where the bot keeps its persistent data.
+    pub database_path: PathBuf,
+    /// How long the "Undo" button stays on a message after issues were created from it.
+    pub undo_window: Duration,
+    /// How often the state of created issues is fetched from GitHub.
+    pub issue_sync_interval: Duration,
+    /// Where the GitHub webhook receiver listens, it is not started if this is not set.
+    pub webhook_address: Option<SocketAddr>,
+    /// The secret GitHub signs webhook deliveries with.
+    pub webhook_secret: Option<String>,
+    /// Where `/healthz` and `/metrics` are served, they are not served if this is not set.
+    pub metrics_address: Option<SocketAddr>,
+    /// How often the watched branches are polled, polling is disabled if this is not set.
+    pub upstream_poll_interval: Option<Duration>,
+    pub watched_branches: Vec<WatchedBranch>,
+    /// How long relayed pushes wait for a veto before their issues get created,
+    /// issues are only created by hand if this is not set.
+    pub auto_create_delay: Option<Duration>,
+    /// The repos guilds can choose to create their issues in, in the `owner/repo` format.
+    /// Only the API docs guild has a repo if this is empty.
+    pub issue_repos: Vec<String>,
+    /// Which pushes are relayed and how, see `routes.example.json`.
+    pub routes: Vec<Route>,
+    /// Which commits get no issue, see `skip_rules.example.json`.
+    pub skip_rules: SkipRules,
+    /// Whether every relayed push gets a thread for its discussion.
+    pub thread_per_push: bool,
+    /// Whether messages in the thread of a push are posted as comments on its issue.
+    pub mirror_threads: bool,
+    /// Where the digest is posted, no digest is posted if this is not set.
+    pub digest_channel: Option<Id<ChannelMarker>>,
+    pub digest_interval: Duration,
+    /// Where privileged actions are posted, they are only stored if this is not set.
+    pub audit_channel: Option<Id<ChannelMarker>>,
+    /// Whether nothing is created, closed or commented on GitHub and no push is relayed,
+    /// what would have been done is reported instead.
+    pub dry_run: bool,
+    /// Where the reports of the dry-run mode are posted, they are only logged if this is not set.
+    pub dry_run_channel: Option<Id<ChannelMarker>>,
+    /// The GitHub App the bot authenticates as, the access token is used if this is not set.
+    pub github_app: Option<GitHubAppConfig>,
+}
+
+impl Config {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let webhook_address = match env::var("WEBHOOK_ADDRESS") {
+            Ok(address) => Some(
+                address
+                    .parse()
+                    .map_err(|err| ConfigError::InvalidAddress("WEBHOOK_ADDRESS", err))?,
+            ),
+            Err(_) => None,
+        };
+        let webhook_secret = env::var("GITHUB_WEBHOOK_SECRET").ok();
+        if webhook_address.is_some() && webhook_secret.is_none() {
+            return Err(ConfigError::Missing("GITHUB_WEBHOOK_SECRET"));
+        }
+
+        let metrics_address = match env::var("METRICS_ADDRESS") {
+            Ok(address) => Some(
+                address
+                    .parse()
+                    .map_err(|err| ConfigError::InvalidAddress("METRICS_ADDRESS", err))?,
+            ),
+            Err(_) => None,
+        };
+
+        let upstream_poll_interval = match parse_var("UPSTREAM_POLL_INTERVAL_SECS", 0)? {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        };
+
+        let auto_create_delay = match parse_var("AUTO_CREATE_DELAY_SECS", 0)? {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        };
+
+        let watched_branches = env::var("UPSTREAM_WATCH")
+            .unwrap_or_else(|_| "discord/discord-api-docs:master".into())
+            .split(',')
+            .map(|watched| match watched.trim().split_once(':') {
+                Some((repo, branch)) if repo.contains('/') && !branch.is_empty() => {
+                    Ok(WatchedBranch {
+                        repo: repo.into(),
+                        branch: branch.into(),
+                    })
+                }
+                _ => Err(ConfigError::InvalidWatchedBranch(watched.into())),
+            })
+            .collect::<Result<_, _>>()?;
+
+        let issue_repos = env::var("ISSUE_REPOS")
+            .unwrap_or_default()
+            .split(',')
+            .filter(|repo| !repo.trim().is_empty())
+            .map(|repo| match repo.trim().split_once('/') {
+                Some((owner, name)) if !owner.is_empty() && !name.is_empty() => {
+                    Ok(repo.trim().to_owned())
+                }
+                _ => Err(ConfigError::InvalidIssueRepo(repo.into())),
+            })
+            .collect::<Result<_, _>>()?;
+
+        let routes_path = env::var("ROUTES_PATH").unwrap_or_else(|_| "routes.json".into());
+        let routes = load_routes(routes_path.as_ref())?;
+
+        let skip_rules_path =
+            env::var("SKIP_RULES_PATH").unwrap_or_else(|_| "skip_rules.json".into());
+        let skip_rules = load_skip_rules(skip_rules_path.as_ref())?;
+
+        let digest_channel = match parse_var("DIGEST_CHANNEL_ID", 0)? {
+            0 => None,
+            id => Some(Id::new(id)),
+        };
+
+        let audit_channel = match parse_var("AUDIT_CHANNEL_ID", 0)? {
+            0 => None,
+            id => Some(Id::new(id)),
+        };
+
+        let dry_run_channel = match parse_var("DRY_RUN_CHANNEL_ID", 0)? {
+            0 => None,
+            id => Some(Id::new(id)),
+        };
+
+        let github_app = match parse_var("GITHUB_APP_ID", 0)? {
+            0 => None,
+            app_id => Some(GitHubAppConfig {
+                app_id,
+                private_key_path: env::var("GITHUB_APP_PRIVATE_KEY_PATH")
+                    .unwrap_or_else(|_| "github-app.pem".into())
+                    .into(),
+                installation_id: match parse_var("GITHUB_APP_INSTALLATION_ID", 0)? {
+                    0 => return Err(ConfigError::Missing("GITHUB_APP_INSTALLATION_ID")),
+                    installation_id => installation_id,
+                },
+            }),
+        };
+
+        Ok(Self {
+            database_path: env::var("DATABASE_PATH")
+                .unwrap_or_else(|_| "data.json".into())
+                .into(),
+            undo_window: Duration::from_secs(parse_var("UNDO_WINDOW_SECS", 600)?),
+            issue_sync_interval: Duration::from_secs(parse_var("ISSUE_SYNC_INTERVAL_SECS", 300)?),
+            webhook_address,
+            webhook_secret,
+            metrics_address,
+            upstream_poll_interval,
+            watched_branches,
+            auto_create_delay,
+            issue_repos,
+            routes,
+            skip_rules,
+            thread_per_push: env::var("THREAD_PER_PUSH").as_deref() == Ok("true"),
+            mirror_threads: env::var("MIRROR_THREADS").as_deref() == Ok("true"),
+            digest_channel,
+            // A week by default
+            digest_interval: Duration::from_secs(parse_var("DIGEST_INTERVAL_SECS", 604800)?),
+            audit_channel,
+            dry_run: env::var("DRY_RUN").as_deref() == Ok("true"),
+            dry_run_channel,
+            github_app,
+        })
+    }
+}
+
+fn parse_var(name: &'static str, default: u64) -> Result<u64, ConfigError> {
+    match env::var(name) {
+        Ok(value) => value
+            .parse()
+            .map_err(|err| ConfigError::InvalidNumber(name, err)),
+        Err(_) => Ok(default),
+    }
+}
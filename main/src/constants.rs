@@ -1,31 +1,51 @@
 use twilight_model::{
     channel::ReactionType,
-    id::{ChannelId, EmojiId, UserId},
+    id::{
+        marker::{ApplicationMarker, ChannelMarker, UserMarker},
+        Id,
+    },
 };
 
-pub const API_DOCS_CHANNEL: ChannelId = unsafe { ChannelId::new_unchecked(881991954676715653_u64) };
-pub const API_DOCS_BOT_ID: UserId = unsafe { UserId::new_unchecked(881992163855065089_u64) };
-pub const ISSUE_MANAGEMENT_USERS: [UserId; 2] = unsafe {
-    [
-        // ITOH
-        UserId::new_unchecked(615542460151496705_u64),
-        // SKILLZ
-        UserId::new_unchecked(130136895395987456_u64),
-    ]
-};
+pub const APPLICATION_ID: Id<ApplicationMarker> = Id::new(906182472507740161_u64);
+
+pub const API_DOCS_CHANNEL: Id<ChannelMarker> = Id::new(881991954676715653_u64);
+/// The upstream repo of the Discord API docs, in the `owner/repo` format.
+pub const API_DOCS_REPO: &str = "discord/discord-api-docs";
+/// The repo the API docs guild creates its issues in, and the one of issues from before repos
+/// could be chosen.
+pub const DEFAULT_ISSUE_REPO: &str = "discordeno/discordeno";
+pub const API_DOCS_BOT_ID: Id<UserMarker> = Id::new(881992163855065089_u64);
+pub const ISSUE_MANAGEMENT_USERS: [Id<UserMarker>; 2] = [
+    // ITOH
+    Id::new(615542460151496705_u64),
+    // SKILLZ
+    Id::new(130136895395987456_u64),
+];
 
-pub const ISSUE_BUTTON_EMOJI: ReactionType = unsafe {
-    ReactionType::Custom {
-        animated: false,
-        id: EmojiId::new_unchecked(754789242412073010_u64),
-        name: None,
-    }
+pub const ISSUE_BUTTON_EMOJI: ReactionType = ReactionType::Custom {
+    animated: false,
+    id: Id::new(754789242412073010_u64),
+    name: None,
 };
 
-pub const REMOVE_BUTTON_EMOJI: ReactionType = unsafe {
-    ReactionType::Custom {
-        animated: false,
-        id: EmojiId::new_unchecked(853559407027683328_u64),
-        name: None,
-    }
+pub const REMOVE_BUTTON_EMOJI: ReactionType = ReactionType::Custom {
+    animated: false,
+    id: Id::new(853559407027683328_u64),
+    name: None,
 };
+
+/// Sent with every request to GitHub, it rejects requests without one.
+pub const GITHUB_USER_AGENT: &str = "Discordeno Issue Creation Bot";
+
+/// GitHub rejects issue titles longer than this.
+pub const ISSUE_TITLE_MAX_LEN: u16 = 256;
+
+pub const ISSUE_OPEN_COLOR: u32 = 0x2da44e;
+pub const ISSUE_ASSIGNED_COLOR: u32 = 0xbf8700;
+pub const ISSUE_CLOSED_COLOR: u32 = 0x6e7781;
+pub const ISSUE_IMPLEMENTED_COLOR: u32 = 0x8250df;
+
+/// The color GitHub uses for push embeds.
+pub const PUSH_EMBED_COLOR: u32 = 0x7289da;
+/// Docs diff reports which contain breaking changes.
+pub const BREAKING_EMBED_COLOR: u32 = 0xcf222e;
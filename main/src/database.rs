@@ -0,0 +1,265 @@
+use crate::{
+    constants::{API_DOCS_CHANNEL, DEFAULT_ISSUE_REPO},
+    github::{GithubCreateIssue, IssueTemplate},
+    routes::Route,
+    types::github::GitHubIssue,
+};
+use chrono::Utc;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, io, path::PathBuf};
+use twilight_model::id::{marker::ChannelMarker, Id};
+
+#[derive(Debug, thiserror::Error)]
+pub enum DatabaseError {
+    #[error("Error while accessing the database file: {0}")]
+    Io(#[from] io::Error),
+    #[error("Error while (de)serializing the database: {0}")]
+    Serde(#[from] serde_json::Error),
+}
+
+/// Everything the bot needs to remember across restarts.
+///
+/// Stored as JSON so new fields can be added with `#[serde(default)]`
+/// without breaking existing database files.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Data {
+    /// Issues created by the bot, keyed by the id of the relayed message they were created from.
+    #[serde(default)]
+    pub created_issues: HashMap<u64, CreatedIssues>,
+    /// Threads opened under relayed messages, keyed by the id of the relayed message.
+    #[serde(default)]
+    pub threads: HashMap<u64, u64>,
+    /// The newest relayed commit of every watched branch, keyed by `owner/repo:branch`.
+    #[serde(default)]
+    pub last_seen: HashMap<String, String>,
+    /// When issues get created automatically (unix timestamp), keyed by the id of the relayed message.
+    #[serde(default)]
+    pub scheduled: HashMap<u64, i64>,
+    /// What the bot did, oldest first. This is what the digest is made of.
+    #[serde(default)]
+    pub history: Vec<HistoryEntry>,
+    /// Unix timestamp of when the last digest was posted.
+    #[serde(default)]
+    pub last_digest_at: i64,
+    /// Issue creation which is not finished yet, keyed by the id of the relayed message.
+    #[serde(default)]
+    pub jobs: HashMap<u64, IssueJob>,
+    /// Every privileged action, oldest first.
+    #[serde(default)]
+    pub audit: Vec<AuditEntry>,
+    /// The settings of every guild which was set up, keyed by the id of the guild.
+    #[serde(default)]
+    pub guilds: HashMap<u64, GuildSettings>,
+    /// The channel of every relayed message, keyed by the id of the message.
+    #[serde(default)]
+    pub relay_channels: HashMap<u64, u64>,
+    /// Unix timestamp of when the issues of a repo were last synced, keyed by `owner/repo`.
+    #[serde(default)]
+    pub issues_synced_at: HashMap<String, i64>,
+    /// The guild of the API docs channel, it keeps the default settings until it is set up.
+    #[serde(default)]
+    pub api_docs_guild: Option<u64>,
+}
+
+/// How the bot works in a guild, set up with `/apidocs settings`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildSettings {
+    /// Where pushes are relayed, nothing is relayed if this is not set.
+    pub relay_channel: Option<u64>,
+    /// The user the GitHub webhook integration posts as in the relay channel.
+    pub webhook_user: Option<u64>,
+    /// The repo issues are created in, in the `owner/repo` format.
+    pub issue_repo: String,
+    /// Who can create, close and comment on issues.
+    pub managers: Vec<u64>,
+    /// Which pushes are relayed and how, see `routes.example.json`.
+    pub routes: Vec<Route>,
+    #[serde(default)]
+    pub template: IssueTemplate,
+}
+
+fn default_issue_repo() -> String {
+    DEFAULT_ISSUE_REPO.into()
+}
+
+/// Who did what to which relayed message, and whether it worked.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Unix timestamp of when the action was taken.
+    pub at: i64,
+    /// The guild the action was taken in, entries from before guilds were recorded have none.
+    #[serde(default)]
+    pub guild_id: Option<u64>,
+    pub actor: u64,
+    /// Like `delete-message` or `/apidocs close`.
+    pub action: String,
+    pub message_id: Option<u64>,
+    /// The commit or compare url of the relayed message.
+    pub upstream: Option<String>,
+    /// Why the action failed, `None` if it succeeded.
+    pub error: Option<String>,
+}
+
+/// The issues of a relayed message which are being created, checkpointed after every issue
+/// so a restart neither loses nor duplicates any of them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssueJob {
+    pub channel_id: u64,
+    /// The user who started the job, 0 if it was started automatically.
+    pub created_by: u64,
+    /// The repo the issues are created in.
+    #[serde(default = "default_issue_repo")]
+    pub repo: String,
+    /// The commit or compare url the issues are created for.
+    #[serde(default)]
+    pub upstream: String,
+    /// The issues still to be created, in order.
+    pub pending: Vec<GithubCreateIssue>,
+    pub created: Vec<GitHubIssue>,
+    /// Why commits were left out, as listed in the summary.
+    pub skipped: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreatedIssues {
+    /// Unix timestamp of when the issues were created.
+    pub created_at: i64,
+    pub created_by: u64,
+    /// The repo the issues were created in.
+    #[serde(default = "default_issue_repo")]
+    pub repo: String,
+    pub issues: Vec<CreatedIssue>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CreatedIssue {
+    pub number: i64,
+    pub html_url: String,
+    #[serde(default)]
+    pub closed: bool,
+    /// The last state seen on GitHub.
+    #[serde(default)]
+    pub status: IssueStatus,
+    #[serde(default)]
+    pub assignees: Vec<String>,
+    /// The merged pull request which implemented the issue.
+    #[serde(default)]
+    pub pull_request: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IssueStatus {
+    #[default]
+    Open,
+    Assigned,
+    Closed,
+    /// Closed by a merged pull request.
+    Implemented,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Unix timestamp of when it happened.
+    pub at: i64,
+    pub event: HistoryEvent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HistoryEvent {
+    /// An upstream push was posted into the API docs channel.
+    Relayed {
+        message_id: u64,
+        title: String,
+    },
+    /// A relayed push was dismissed with the delete button.
+    Dismissed {
+        message_id: u64,
+    },
+    IssuesCreated {
+        message_id: u64,
+        numbers: Vec<i64>,
+    },
+    IssueClosed {
+        /// Empty for issues closed before the repo was recorded.
+        #[serde(default)]
+        repo: String,
+        number: i64,
+    },
+}
+
+impl Data {
+    /// Remember that something happened just now.
+    pub fn log(&mut self, event: HistoryEvent) {
+        self.history.push(HistoryEntry {
+            at: Utc::now().timestamp(),
+            event,
+        });
+    }
+
+    /// Find the id of the relayed message the given bot created issue belongs to.
+    ///
+    /// Only messages in the relay channel of the guild count, so no guild can touch the issues
+    /// of another one which uses the same repo.
+    pub fn message_of_issue(&self, settings: &GuildSettings, number: i64) -> Option<u64> {
+        let relay_channel = settings.relay_channel?;
+
+        self.created_issues
+            .iter()
+            .find(|(message_id, created)| {
+                self.channel_of(**message_id).get() == relay_channel
+                    && created.repo == settings.issue_repo
+                    && created.issues.iter().any(|issue| issue.number == number)
+            })
+            .map(|(message_id, _)| *message_id)
+    }
+
+    /// The channel a message was relayed into, messages relayed before guilds had their own
+    /// channels are all in the API docs channel.
+    pub fn channel_of(&self, message_id: u64) -> Id<ChannelMarker> {
+        self.relay_channels
+            .get(&message_id)
+            .map_or(API_DOCS_CHANNEL, |channel_id| Id::new(*channel_id))
+    }
+}
+
+pub struct Database {
+    path: PathBuf,
+    data: RwLock<Data>,
+}
+
+impl Database {
+    /// Load the database from the given file, starting empty if it does not exist yet.
+    pub fn open(path: PathBuf) -> Result<Self, DatabaseError> {
+        let data = match fs::read(&path) {
+            Ok(raw) => serde_json::from_slice(&raw)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Data::default(),
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(Self {
+            path,
+            data: RwLock::new(data),
+        })
+    }
+
+    pub fn read<T>(&self, f: impl FnOnce(&Data) -> T) -> T {
+        f(&self.data.read())
+    }
+
+    /// Change the data and write it to disk right away.
+    pub fn write<T>(&self, f: impl FnOnce(&mut Data) -> T) -> Result<T, DatabaseError> {
+        let mut data = self.data.write();
+        let result = f(&mut data);
+
+        // Write to a temporary file first so a crash can not leave a half written database behind
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, serde_json::to_vec(&*data)?)?;
+        fs::rename(tmp_path, &self.path)?;
+
+        Ok(result)
+    }
+}
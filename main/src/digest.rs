@@ -0,0 +1,228 @@
+use crate::{
+    constants::PUSH_EMBED_COLOR,
+    database::{Data, HistoryEvent},
+    metrics::Recorded,
+    types::State,
+};
+use chrono::Utc;
+use std::{collections::BTreeMap, error::Error, time::Duration};
+use twilight_model::{
+    channel::embed::Embed,
+    id::{marker::ChannelMarker, Id},
+};
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder};
+
+/// Every issue carries this label, so it says nothing in the digest.
+const COMMON_LABEL: &str = "api-docs-commits";
+/// How long the history is kept after it made it into a digest.
+const HISTORY_RETENTION_SECS: i64 = 90 * 24 * 60 * 60;
+
+type DigestResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Post a digest of the history into the digest channel every interval.
+pub async fn post_digests(state: State, channel_id: Id<ChannelMarker>) {
+    let interval = state.config.digest_interval.as_secs() as i64;
+
+    // The first digest covers the time since the bot started
+    let mut last_digest_at = state.database.read(|data| data.last_digest_at);
+    if last_digest_at == 0 {
+        last_digest_at = Utc::now().timestamp();
+        if let Err(err) = state
+            .database
+            .write(|data| data.last_digest_at = last_digest_at)
+        {
+            tracing::warn!("Could not store when the digest period started: {}", err);
+        }
+    }
+
+    loop {
+        // Waiting for the due time instead of a fixed interval keeps the schedule across restarts
+        let wait = last_digest_at + interval - Utc::now().timestamp();
+        if wait > 0 {
+            tokio::time::sleep(Duration::from_secs(wait as u64)).await;
+        }
+
+        let now = Utc::now().timestamp();
+        match post_digest(&state, channel_id, last_digest_at, now).await {
+            Ok(()) => last_digest_at = now,
+            Err(err) => {
+                tracing::warn!("Could not post the digest: {}", err);
+                // Try again later instead of spinning
+                tokio::time::sleep(Duration::from_secs(60 * 60)).await;
+            }
+        }
+    }
+}
+
+async fn post_digest(
+    state: &State,
+    channel_id: Id<ChannelMarker>,
+    from: i64,
+    to: i64,
+) -> DigestResult<()> {
+    let embed = state.database.read(|data| digest_embed(data, from, to))?;
+
+    state
+        .http
+        .create_message(channel_id)
+        .embeds(&[embed])?
+        .exec()
+        .recorded()
+        .await?;
+
+    state.database.write(|data| {
+        data.last_digest_at = to;
+        data.history
+            .retain(|entry| entry.at > to - HISTORY_RETENTION_SECS);
+    })?;
+
+    Ok(())
+}
+
+/// List at most 10 entries, the rest is only counted.
+fn list(entries: Vec<String>) -> String {
+    if entries.is_empty() {
+        return "None".into();
+    }
+
+    let mut lines: Vec<_> = entries.iter().take(10).cloned().collect();
+    if entries.len() > 10 {
+        lines.push(format!("and {} more", entries.len() - 10));
+    }
+
+    // Keep every field short so all of them fit into the size limit of an embed
+    lines.join("\n").chars().take(350).collect()
+}
+
+/// `owner/repo#123`, only `#123` if the repo is not known.
+fn issue_ref(repo: &str, number: i64) -> String {
+    format!("{}#{}", repo, number)
+}
+
+fn digest_embed(data: &Data, from: i64, to: i64) -> DigestResult<Embed> {
+    let period: Vec<_> = data
+        .history
+        .iter()
+        .filter(|entry| entry.at > from && entry.at <= to)
+        .collect();
+
+    let title_of = |message_id: u64| {
+        data.history.iter().find_map(|entry| match &entry.event {
+            HistoryEvent::Relayed {
+                message_id: relayed,
+                title,
+            } if *relayed == message_id => Some(title.clone()),
+            _ => None,
+        })
+    };
+
+    let relayed: Vec<_> = period
+        .iter()
+        .filter_map(|entry| match &entry.event {
+            HistoryEvent::Relayed { title, .. } => Some(title.clone()),
+            _ => None,
+        })
+        .collect();
+
+    let dismissed: Vec<_> = period
+        .iter()
+        .filter_map(|entry| match &entry.event {
+            HistoryEvent::Dismissed { message_id } => {
+                Some(title_of(*message_id).unwrap_or_else(|| format!("Message {}", message_id)))
+            }
+            _ => None,
+        })
+        .collect();
+
+    // Guilds create their issues in different repos, so the numbers alone are ambiguous
+    let created: Vec<_> = period
+        .iter()
+        .filter_map(|entry| match &entry.event {
+            HistoryEvent::IssuesCreated {
+                message_id,
+                numbers,
+            } => {
+                let repo = data
+                    .created_issues
+                    .get(message_id)
+                    .map_or("", |created| created.repo.as_str());
+
+                Some(numbers.iter().map(move |number| issue_ref(repo, *number)))
+            }
+            _ => None,
+        })
+        .flatten()
+        .collect();
+
+    let closed: Vec<_> = period
+        .iter()
+        .filter_map(|entry| match &entry.event {
+            HistoryEvent::IssueClosed { repo, number } => Some(issue_ref(repo, *number)),
+            _ => None,
+        })
+        .collect();
+
+    let mut open_by_label: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (repo, issue) in data
+        .created_issues
+        .values()
+        .flat_map(|created| {
+            created
+                .issues
+                .iter()
+                .map(move |issue| (&created.repo, issue))
+        })
+        .filter(|(_, issue)| !issue.closed)
+    {
+        let link = issue_ref(repo, issue.number);
+        let mut labels = issue
+            .labels
+            .iter()
+            .filter(|label| label.as_str() != COMMON_LABEL)
+            .peekable();
+
+        if labels.peek().is_none() {
+            open_by_label
+                .entry("unlabeled".into())
+                .or_default()
+                .push(link);
+        } else {
+            for label in labels {
+                open_by_label
+                    .entry(label.clone())
+                    .or_default()
+                    .push(link.clone());
+            }
+        }
+    }
+
+    let mut embed = EmbedBuilder::new()
+        .title("Digest")
+        .description(format!("From <t:{}:f> until <t:{}:f>", from, to))
+        .color(PUSH_EMBED_COLOR)
+        .field(EmbedFieldBuilder::new(
+            format!("Relayed pushes ({})", relayed.len()),
+            list(relayed),
+        ))
+        .field(EmbedFieldBuilder::new(
+            format!("Dismissed pushes ({})", dismissed.len()),
+            list(dismissed),
+        ))
+        .field(
+            EmbedFieldBuilder::new(format!("Issues created ({})", created.len()), list(created))
+                .inline(),
+        )
+        .field(
+            EmbedFieldBuilder::new(format!("Issues closed ({})", closed.len()), list(closed))
+                .inline(),
+        );
+
+    for (label, issues) in open_by_label.into_iter().take(10) {
+        embed = embed.field(EmbedFieldBuilder::new(
+            format!("Open: {} ({})", label, issues.len()),
+            list(issues),
+        ));
+    }
+
+    Ok(embed.validate()?.build())
+}
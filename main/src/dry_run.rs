@@ -0,0 +1,118 @@
+use crate::{github::GithubCreateIssue, metrics::Recorded, routes::BranchAction, types::State};
+use serde::Serialize;
+use twilight_model::{channel::embed::Embed, http::attachment::Attachment};
+use twilight_util::builder::embed::EmbedBuilder;
+
+/// Something the bot would have done, which is reported instead in dry-run mode.
+#[derive(Debug, Serialize)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+pub enum DryRunAction {
+    /// Post a push into the API docs channel and replace the original webhook message.
+    Relay {
+        channel_id: u64,
+        repo: String,
+        branch: String,
+        route: BranchAction,
+        content: String,
+        embeds: Vec<Embed>,
+        /// Only set when the issues of the push are created without anyone pressing a button.
+        issues: Vec<GithubCreateIssue>,
+    },
+    CreateIssues {
+        repo: String,
+        message_id: u64,
+        issues: Vec<GithubCreateIssue>,
+    },
+    CloseIssue {
+        repo: String,
+        number: i64,
+        invalid: bool,
+    },
+    Comment {
+        repo: String,
+        number: i64,
+        body: String,
+    },
+}
+
+impl DryRunAction {
+    /// A short description, this is also what interactions get as their response.
+    pub fn summary(&self) -> String {
+        let summary = match self {
+            Self::Relay { repo, branch, .. } => {
+                format!("Would relay a push to `{}:{}`", repo, branch)
+            }
+            Self::CreateIssues { issues, .. } => format!("Would create {} issue(s)", issues.len()),
+            Self::CloseIssue { number, .. } => format!("Would close issue #{}", number),
+            Self::Comment { number, .. } => format!("Would comment on issue #{}", number),
+        };
+
+        format!("Dry run: {}.", summary)
+    }
+}
+
+/// Report an action instead of taking it, it is logged as JSON and posted into the dry-run
+/// channel, if there is one.
+///
+/// Reporting never fails the handler, problems are only logged.
+pub async fn report(state: &State, action: DryRunAction) {
+    let summary = action.summary();
+    let json = match serde_json::to_string_pretty(&action) {
+        Ok(json) => json,
+        Err(err) => {
+            tracing::warn!("Could not serialize a dry-run action: {}", err);
+            return;
+        }
+    };
+    tracing::info!(action = %json, "{}", summary);
+
+    let channel_id = match state.config.dry_run_channel {
+        Some(channel_id) => channel_id,
+        None => return,
+    };
+
+    // Large payloads do not fit into an embed, they are attached as a file instead
+    let code_block = format!("```json\n{}\n```", json);
+    let (description, attachments) = if code_block.chars().count() <= 4096 {
+        (code_block, vec![])
+    } else {
+        (
+            "The full action is attached.".to_owned(),
+            vec![Attachment::from_bytes(
+                "dry-run.json".into(),
+                json.into_bytes(),
+                0,
+            )],
+        )
+    };
+
+    let embed = match EmbedBuilder::new()
+        .title(summary)
+        .description(description)
+        .validate()
+    {
+        Ok(embed) => embed.build(),
+        Err(err) => {
+            tracing::warn!("Could not build the dry-run embed: {}", err);
+            return;
+        }
+    };
+
+    let embeds = [embed];
+    let request = match state
+        .http
+        .create_message(channel_id)
+        .embeds(&embeds)
+        .and_then(|request| request.attachments(&attachments))
+    {
+        Ok(request) => request,
+        Err(err) => {
+            tracing::warn!("Could not build the dry-run message: {}", err);
+            return;
+        }
+    };
+
+    if let Err(err) = request.exec().recorded().await {
+        tracing::warn!("Could not post into the dry-run channel: {}", err);
+    }
+}
@@ -0,0 +1,473 @@
+use crate::{
+    constants::{BREAKING_EMBED_COLOR, PUSH_EMBED_COLOR},
+    report::DocsReport,
+    types::github::{Author, GitHubCommit, GitHubCompare},
+};
+use std::{collections::BTreeMap, error::Error};
+use twilight_model::channel::embed::Embed;
+use twilight_util::builder::embed::{
+    EmbedAuthorBuilder, EmbedBuilder, EmbedFieldBuilder, ImageSource,
+};
+
+/// Most embeds a single message can hold.
+const MAX_EMBEDS_PER_MESSAGE: usize = 10;
+/// Most characters all embeds of a single message can hold together.
+const MAX_EMBED_CHARS_PER_MESSAGE: usize = 6000;
+
+/// A commit as it is listed in a relay embed.
+pub struct RelayCommit<'a> {
+    pub sha: &'a str,
+    pub url: &'a str,
+    pub message: &'a str,
+    pub author: &'a str,
+}
+
+/// Build an embed in the style of the GitHub Discord webhook integration.
+///
+/// `url` should point to the commit for single commits and to the compare otherwise,
+/// this is what the issue flows read back from the relayed message.
+pub fn push_embed(
+    repo_name: &str,
+    branch: &str,
+    url: String,
+    commits: &[RelayCommit<'_>],
+    sender: Option<&Author>,
+) -> Result<Embed, Box<dyn Error + Send + Sync>> {
+    // Keep the description short like the embeds from the Discord webhook integration
+    let description = commits
+        .iter()
+        .take(5)
+        .map(|commit| {
+            format!(
+                "[`{}`]({}) {} - {}",
+                &commit.sha[..7.min(commit.sha.len())],
+                commit.url,
+                truncate(commit.message.lines().next().unwrap_or_default(), 100),
+                commit.author
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut embed = EmbedBuilder::new()
+        .title(format!(
+            "[{}:{}] {} new commit{}",
+            repo_name,
+            branch,
+            commits.len(),
+            if commits.len() == 1 { "" } else { "s" }
+        ))
+        .url(url)
+        .description(truncate(&description, 4096))
+        .color(PUSH_EMBED_COLOR);
+
+    if let Some(sender) = sender {
+        let mut builder =
+            EmbedAuthorBuilder::new(sender.login.clone()).url(sender.html_url.clone());
+        if let Ok(avatar) = ImageSource::url(sender.avatar_url.clone()) {
+            builder = builder.icon_url(avatar);
+        }
+
+        embed = embed.author(builder);
+    }
+
+    Ok(embed.validate()?.build())
+}
+
+/// Build the push embed for everything in a compare, `None` if there is nothing in it.
+pub fn compare_embed(
+    repo: &str,
+    branch: &str,
+    compare: &GitHubCompare,
+) -> Result<Option<Embed>, Box<dyn Error + Send + Sync>> {
+    // Force pushes can leave nothing new to relay
+    if compare.commits.is_empty() {
+        return Ok(None);
+    }
+
+    let url = if compare.commits.len() == 1 {
+        compare.commits[0].html_url.clone()
+    } else {
+        compare.html_url.clone()
+    };
+
+    let commits: Vec<_> = compare
+        .commits
+        .iter()
+        .map(|commit| RelayCommit {
+            sha: &commit.sha,
+            url: &commit.html_url,
+            message: &commit.commit.message,
+            author: &commit.commit.author.name,
+        })
+        .collect();
+
+    let repo_name = repo.rsplit('/').next().unwrap_or(repo);
+
+    Ok(Some(push_embed(
+        repo_name,
+        branch,
+        url,
+        &commits,
+        compare.commits.last().map(|commit| &commit.author),
+    )?))
+}
+
+/// Build the relay embed of a push from the fetched commits, with their stats and touched files.
+///
+/// `url` is kept as it is, the issue flows read the commits back from it.
+pub fn relay_embed(
+    repo_name: &str,
+    branch: &str,
+    url: String,
+    commits: &[GitHubCommit],
+    color: Option<u32>,
+) -> Result<Embed, Box<dyn Error + Send + Sync>> {
+    let mut lines: Vec<_> = commits
+        .iter()
+        .take(10)
+        .map(|commit| {
+            format!(
+                "[`{}`]({}) {} - {} (+{} -{})",
+                &commit.sha[..7.min(commit.sha.len())],
+                commit.html_url,
+                truncate(
+                    commit.commit.message.lines().next().unwrap_or_default(),
+                    100
+                ),
+                commit.commit.author.name,
+                commit.stats.additions,
+                commit.stats.deletions
+            )
+        })
+        .collect();
+    if commits.len() > 10 {
+        lines.push(format!("and {} more", commits.len() - 10));
+    }
+
+    // The same file is often touched by several commits of a push
+    let mut files: BTreeMap<&str, (i64, i64)> = BTreeMap::new();
+    for file in commits.iter().flat_map(|commit| &commit.files) {
+        let (additions, deletions) = files.entry(&file.filename).or_default();
+        *additions += file.additions;
+        *deletions += file.deletions;
+    }
+
+    let mut touched: Vec<_> = files
+        .iter()
+        .take(10)
+        .map(|(filename, (additions, deletions))| {
+            format!("`{}` +{} -{}", filename, additions, deletions)
+        })
+        .collect();
+    if files.len() > 10 {
+        touched.push(format!("and {} more", files.len() - 10));
+    }
+
+    let (additions, deletions) = files
+        .values()
+        .fold((0, 0), |(total_add, total_del), (add, del)| {
+            (total_add + add, total_del + del)
+        });
+
+    let mut embed = EmbedBuilder::new()
+        .title(format!(
+            "[{}:{}] {} new commit{}",
+            repo_name,
+            branch,
+            commits.len(),
+            if commits.len() == 1 { "" } else { "s" }
+        ))
+        .url(url)
+        .description(truncate(&lines.join("\n"), 4096))
+        .color(color.unwrap_or(PUSH_EMBED_COLOR));
+
+    if !touched.is_empty() {
+        embed = embed.field(EmbedFieldBuilder::new(
+            format!(
+                "{} file{} changed (+{} -{})",
+                files.len(),
+                if files.len() == 1 { "" } else { "s" },
+                additions,
+                deletions
+            ),
+            truncate(&touched.join("\n"), 1024),
+        ));
+    }
+
+    // Commits without a linked GitHub account have no login and avatar
+    if let Some(author) = commits
+        .last()
+        .map(|commit| &commit.author)
+        .filter(|author| !author.login.is_empty())
+    {
+        let mut builder =
+            EmbedAuthorBuilder::new(author.login.clone()).url(author.html_url.clone());
+        if let Ok(avatar) = ImageSource::url(author.avatar_url.clone()) {
+            builder = builder.icon_url(avatar);
+        }
+
+        embed = embed.author(builder);
+    }
+
+    Ok(embed.validate()?.build())
+}
+
+/// Cut a text down to the given amount of characters, marking that it was cut.
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_owned();
+    }
+
+    let mut truncated: String = text.chars().take(max - 1).collect();
+    truncated.push('…');
+
+    truncated
+}
+
+/// Build the embeds of a docs diff report, an overview followed by one embed per page with detected changes.
+pub fn report_embeds(report: &DocsReport) -> Result<Vec<Embed>, Box<dyn Error + Send + Sync>> {
+    let color = if report.is_breaking() {
+        BREAKING_EMBED_COLOR
+    } else {
+        PUSH_EMBED_COLOR
+    };
+
+    let mut overview = vec![format!(
+        "{} commits, {} changed pages",
+        report.total_commits,
+        report.pages.len()
+    )];
+    if report.is_breaking() {
+        overview.push("**Contains breaking changes**".into());
+    }
+    if !report.omissions.is_empty() {
+        overview.push("**Incomplete, GitHub left things out**".into());
+        overview.extend(report.omissions.iter().cloned());
+    }
+    overview.extend(
+        report
+            .totals()
+            .iter()
+            .map(|(title, count)| format!("{}: {}", title, count)),
+    );
+
+    let other_pages: Vec<_> = report
+        .pages
+        .iter()
+        .filter(|page| page.changes.is_empty())
+        .map(|page| {
+            format!(
+                "`{}` ({}, +{} -{})",
+                page.filename, page.status, page.additions, page.deletions
+            )
+        })
+        .collect();
+    if !other_pages.is_empty() {
+        overview.push(String::new());
+        overview.push("**Other changed pages**".into());
+        overview.extend(other_pages);
+    }
+
+    let mut embeds = vec![EmbedBuilder::new()
+        .title(truncate(
+            &format!("Docs changes {}...{}", report.from, report.to),
+            256,
+        ))
+        .url(report.html_url.clone())
+        .description(truncate(&overview.join("\n"), 4096))
+        .color(color)
+        .validate()?
+        .build()];
+
+    for page in report.pages.iter().filter(|page| !page.changes.is_empty()) {
+        let mut embed = EmbedBuilder::new()
+            .title(truncate(&page.filename, 256))
+            .url(report.html_url.clone())
+            .description(format!(
+                "{}, +{} -{}",
+                page.status, page.additions, page.deletions
+            ))
+            .color(color);
+
+        // Short of the field limit so even a page with every kind of change fits into a message
+        for (title, entries) in page.changes.sections() {
+            embed = embed.field(EmbedFieldBuilder::new(
+                title,
+                truncate(&entries.join("\n"), 900),
+            ));
+        }
+
+        embeds.push(embed.validate()?.build());
+    }
+
+    Ok(embeds)
+}
+
+fn embed_chars(embed: &Embed) -> usize {
+    let text = |text: &Option<String>| text.as_ref().map_or(0, |text| text.chars().count());
+
+    text(&embed.title)
+        + text(&embed.description)
+        + embed
+            .fields
+            .iter()
+            .map(|field| field.name.chars().count() + field.value.chars().count())
+            .sum::<usize>()
+        + embed
+            .footer
+            .as_ref()
+            .map_or(0, |footer| footer.text.chars().count())
+        + embed
+            .author
+            .as_ref()
+            .map_or(0, |author| author.name.chars().count())
+}
+
+/// Cut an embed down to what a single message can hold, first its description and then its
+/// last fields.
+fn clamp_embed(mut embed: Embed) -> Embed {
+    let excess = |embed: &Embed| embed_chars(embed).saturating_sub(MAX_EMBED_CHARS_PER_MESSAGE);
+
+    let over = excess(&embed);
+    if over > 0 {
+        if let Some(description) = &embed.description {
+            let keep = description.chars().count().saturating_sub(over);
+            embed.description = (keep > 0).then(|| truncate(description, keep));
+        }
+    }
+
+    while excess(&embed) > 0 && embed.fields.pop().is_some() {}
+
+    embed
+}
+
+/// Split embeds into groups which fit into a single message each, embeds which do not fit into
+/// any message on their own are cut down.
+pub fn split_embeds(embeds: Vec<Embed>) -> Vec<Vec<Embed>> {
+    let mut messages: Vec<Vec<Embed>> = vec![];
+    let mut chars = 0;
+
+    for embed in embeds {
+        let embed = clamp_embed(embed);
+        let len = embed_chars(&embed);
+
+        match messages.last_mut() {
+            Some(message)
+                if message.len() < MAX_EMBEDS_PER_MESSAGE
+                    && chars + len <= MAX_EMBED_CHARS_PER_MESSAGE =>
+            {
+                chars += len;
+                message.push(embed);
+            }
+            _ => {
+                chars = len;
+                messages.push(vec![embed]);
+            }
+        }
+    }
+
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn embed(description: &str) -> Embed {
+        EmbedBuilder::new()
+            .title("title")
+            .description(description)
+            .build()
+    }
+
+    fn lens(messages: &[Vec<Embed>]) -> Vec<usize> {
+        messages.iter().map(Vec::len).collect()
+    }
+
+    #[test]
+    fn short_texts_are_kept() {
+        assert_eq!(truncate("commit", 6), "commit");
+        assert_eq!(truncate("", 1), "");
+    }
+
+    #[test]
+    fn long_texts_are_cut_with_a_marker() {
+        assert_eq!(truncate("commits", 6), "commi…");
+        assert_eq!(truncate("commits", 1), "…");
+        // Characters are counted, not bytes
+        assert_eq!(truncate("ééééé", 4), "ééé…");
+    }
+
+    #[test]
+    fn embeds_are_counted_by_their_texts() {
+        let embed = EmbedBuilder::new()
+            .title("ab")
+            .description("cdé")
+            .field(EmbedFieldBuilder::new("f", "gh"))
+            .build();
+
+        assert_eq!(embed_chars(&embed), 8);
+    }
+
+    #[test]
+    fn small_embeds_share_a_message() {
+        assert_eq!(
+            lens(&split_embeds(vec![embed("a"), embed("b"), embed("c")])),
+            [3]
+        );
+        assert!(split_embeds(vec![]).is_empty());
+    }
+
+    #[test]
+    fn messages_hold_at_most_ten_embeds() {
+        assert_eq!(lens(&split_embeds(vec![embed("a"); 23])), [10, 10, 3]);
+    }
+
+    #[test]
+    fn messages_hold_at_most_6000_characters() {
+        // 5 + 2995 characters each, two fill a message exactly
+        let embeds = vec![embed(&"a".repeat(2995)); 5];
+        let messages = split_embeds(embeds);
+
+        assert_eq!(lens(&messages), [2, 2, 1]);
+        for message in &messages {
+            assert!(message.iter().map(embed_chars).sum::<usize>() <= MAX_EMBED_CHARS_PER_MESSAGE);
+        }
+    }
+
+    #[test]
+    fn embeds_which_are_too_large_are_cut() {
+        let mut large = embed(&"a".repeat(4096));
+        for _ in 0..3 {
+            large
+                .fields
+                .push(EmbedFieldBuilder::new("name", "b".repeat(1000)).build());
+        }
+        assert_eq!(embed_chars(&large), 5 + 4096 + 3 * 1004);
+
+        let messages = split_embeds(vec![embed("small"), large]);
+
+        assert_eq!(lens(&messages), [1, 1]);
+        let clamped = &messages[1][0];
+        assert_eq!(embed_chars(clamped), MAX_EMBED_CHARS_PER_MESSAGE);
+        assert_eq!(clamped.fields.len(), 3);
+        assert!(clamped.description.as_ref().unwrap().ends_with('…'));
+    }
+
+    #[test]
+    fn fields_go_when_the_description_is_not_enough() {
+        let mut large = embed("a");
+        for _ in 0..7 {
+            large
+                .fields
+                .push(EmbedFieldBuilder::new("name", "b".repeat(1000)).build());
+        }
+
+        let clamped = clamp_embed(large);
+
+        assert_eq!(clamped.description, None);
+        assert_eq!(clamped.fields.len(), 5);
+        assert!(embed_chars(&clamped) <= MAX_EMBED_CHARS_PER_MESSAGE);
+    }
+}
@@ -1,6 +1,20 @@
-use std::{env, thread, time::Duration};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use std::{
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
 
-use crate::types::github::{Commit, GitHubCommit, GitHubCompare};
+use crate::{
+    classify::ChangeSummary,
+    constants::{API_DOCS_REPO, GITHUB_USER_AGENT},
+    github_auth::{Access, GitHubAuth, GitHubAuthError},
+    metrics::METRICS,
+    typediff::suggest_type_diff,
+    types::github::{GitHubBranch, GitHubCommit, GitHubCompare, GitHubIssue, TimelineEvent},
+};
+
+/// Pause between creating two issues, GitHub limits how fast content can be created.
+pub const ISSUE_CREATION_PAUSE: Duration = Duration::from_secs(5);
 
 #[derive(Debug, thiserror::Error)]
 pub enum CreateIssuesError {
@@ -8,10 +22,12 @@ pub enum CreateIssuesError {
     NoHashFound,
     #[error("Error from reqwest: {0}")]
     ReqwestError(#[from] reqwest::Error),
+    #[error("Could not authenticate against GitHub: {0}")]
+    Auth(#[from] GitHubAuthError),
 }
 
 fn construct(message: String) -> (String, Option<String>) {
-    let mut parts: Vec<_> = message.split('\n').into_iter().collect();
+    let mut parts: Vec<_> = message.split('\n').collect();
 
     let title = parts.remove(0).to_owned();
     let description = if !parts.is_empty() {
@@ -23,110 +39,460 @@ fn construct(message: String) -> (String, Option<String>) {
     (title, description)
 }
 
-pub async fn create_issues(url: String) -> Result<(), CreateIssuesError> {
-    fn build_request_headers(mut request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
-        request = request.header(reqwest::header::USER_AGENT, "Discordeno Issue Creation Bot");
-        request = request.header(
-            reqwest::header::AUTHORIZATION,
-            format!("token {}", env::var("GITHUB_ACCESS_TOKEN").unwrap()),
-        );
+/// Add the headers every request needs, authenticated for the repo the request concerns.
+async fn build_request_headers(
+    auth: &GitHubAuth,
+    mut request: reqwest::RequestBuilder,
+    repo: Option<&str>,
+    access: Access,
+) -> Result<reqwest::RequestBuilder, CreateIssuesError> {
+    request = request.header(reqwest::header::USER_AGENT, GITHUB_USER_AGENT);
+    request = request.header(
+        reqwest::header::AUTHORIZATION,
+        auth.authorization(repo, access).await?,
+    );
+
+    Ok(request)
+}
+
+/// Send a request to the GitHub API, recording it in the metrics.
+pub(crate) async fn send(
+    request: reqwest::RequestBuilder,
+) -> Result<reqwest::Response, reqwest::Error> {
+    let started = Instant::now();
+    let result = request.send().await;
 
-        request
+    let remaining = result.as_ref().ok().and_then(|res| {
+        res.headers()
+            .get("x-ratelimit-remaining")?
+            .to_str()
+            .ok()?
+            .parse()
+            .ok()
+    });
+    let failed = !matches!(&result, Ok(res) if res.status().is_success());
+    tracing::debug!(
+        status = result.as_ref().ok().map(|res| res.status().as_u16()),
+        elapsed_ms = started.elapsed().as_millis() as u64,
+        failed,
+        "GitHub request"
+    );
+    METRICS.github.record(started.elapsed(), failed);
+    if let Some(remaining) = remaining {
+        METRICS.set_github_rate_limit(remaining);
     }
 
-    if let Ok(client) = reqwest::Client::builder().build() {
-        let hash_part = url.split('/').last();
-        if hash_part == None {
-            return Err(CreateIssuesError::NoHashFound);
-        }
+    result
+}
 
-        let mut commits: Vec<Commit> = vec![];
-
-        // Check whether its a normal commit url or an url to a compare.
-        if !hash_part.unwrap().contains("...") {
-            // Its a normal commit URL
-            // First get the issue
-            let mut request = client.get(format!(
-                "https://api.github.com/repos/discord/discord-api-docs/commits/{}",
-                hash_part.unwrap()
-            ));
-
-            request = build_request_headers(request);
-            let res = request.send().await;
-            let commit_data = res.unwrap().json::<GitHubCommit>().await?;
-            commits.push(commit_data.commit);
-
-            // if let Ok(res) = request.send().await {
-            //     let commit_data = res.json::<GitHubCommit>().await?;
-            //     commits.push(commit_data.commit)
-            // }
-        } else {
-            // Its a compare URL
-            // First get the issue
-            let mut request = client.get(format!(
-                "https://api.github.com/repos/discord/discord-api-docs/compare/{}",
-                hash_part.unwrap()
-            ));
-
-            request = build_request_headers(request);
-            let res = request.send().await;
-            let compare_data = res.unwrap().json::<GitHubCompare>().await?;
-            commits = compare_data
-                .commits
-                .into_iter()
-                .map(|cmp| cmp.commit)
-                .collect();
-        }
+/// Whether GitHub accepts the access token, this also refreshes the rate limit metric.
+pub async fn check_token(auth: &GitHubAuth) -> Result<bool, CreateIssuesError> {
+    let client = reqwest::Client::builder().build()?;
+
+    let mut request = client.get("https://api.github.com/rate_limit");
+    request = build_request_headers(auth, request, None, Access::Read).await?;
+
+    let status = send(request).await?.status();
+
+    Ok(status.is_success())
+}
+
+/// Most commit requests of a compare which are in flight at once.
+const CONCURRENT_COMMIT_REQUESTS: usize = 4;
+
+/// The `owner/repo` a GitHub url like `https://github.com/{owner}/{repo}/commit/{hash}` points to,
+/// this way relays of every watched repo can be handled.
+fn repo_of_url(url: &str) -> String {
+    url.strip_prefix("https://github.com/")
+        .and_then(|path| {
+            let mut parts = path.split('/');
+            Some(format!("{}/{}", parts.next()?, parts.next()?))
+        })
+        .unwrap_or_else(|| API_DOCS_REPO.into())
+}
+
+/// Fetch every commit behind a commit or compare url posted by the GitHub webhook.
+///
+/// The commits come with their stats and files, which the skip rules need.
+pub async fn fetch_commits(
+    auth: &GitHubAuth,
+    url: &str,
+) -> Result<Vec<GitHubCommit>, CreateIssuesError> {
+    fetch_commits_with(auth, url, |_| true).await
+}
+
+/// Like [`fetch_commits`], but the commits of a compare only get their stats and files if
+/// `needs_details` asks for them, which saves a request per commit.
+#[tracing::instrument(level = "debug", skip(auth, needs_details))]
+pub async fn fetch_commits_with(
+    auth: &GitHubAuth,
+    url: &str,
+    needs_details: impl Fn(&GitHubCommit) -> bool,
+) -> Result<Vec<GitHubCommit>, CreateIssuesError> {
+    let hash_part = match url.split('/').next_back() {
+        Some(hash_part) => hash_part,
+        None => return Err(CreateIssuesError::NoHashFound),
+    };
+    let repo = repo_of_url(url);
+
+    // Check whether its a normal commit url or an url to a compare.
+    if !hash_part.contains("...") {
+        return Ok(vec![fetch_commit(auth, &repo, hash_part).await?]);
+    }
+
+    let client = reqwest::Client::builder().build()?;
+
+    let mut request = client.get(format!(
+        "https://api.github.com/repos/{}/compare/{}",
+        repo, hash_part
+    ));
+
+    request = build_request_headers(auth, request, Some(repo.as_str()), Access::Read).await?;
+    let compare_data = send(request)
+        .await?
+        .error_for_status()?
+        .json::<GitHubCompare>()
+        .await?;
 
-        let mut len = commits.len();
-
-        for commit in commits {
-            let url = match commit.url.split('/').last() {
-                Some(hash) => format!(
-                    "https://github.com/discord/discord-api-docs/commit/{}",
-                    hash
-                ),
-                None => commit.url,
-            };
-
-            let mut request =
-                client.post("https://api.github.com/repos/discordeno/discordeno/issues");
-
-            request = build_request_headers(request);
-            request = request.header(reqwest::header::ACCEPT, "application/vnd.github.v3+json");
-            let parsed = construct(commit.message);
-            request
-                .json(&GithubCreateIssue::new(parsed.0, parsed.1, url))
-                .send()
-                .await?;
-
-            len -= 1;
-            if len != 0 {
-                thread::sleep(Duration::from_secs(5));
+    // Commits of a compare come without their stats and files
+    let repo = repo.as_str();
+    stream::iter(compare_data.commits.into_iter().map(|commit| {
+        let commit = GitHubCommit {
+            sha: commit.sha,
+            node_id: commit.node_id,
+            commit: commit.commit,
+            url: commit.url,
+            html_url: commit.html_url,
+            comments_url: commit.comments_url,
+            author: commit.author,
+            committer: commit.committer,
+            parents: commit.parents,
+            ..Default::default()
+        };
+        let needs_details = needs_details(&commit);
+
+        async move {
+            if needs_details {
+                fetch_commit(auth, repo, &commit.sha).await
+            } else {
+                Ok(commit)
             }
         }
+    }))
+    .buffered(CONCURRENT_COMMIT_REQUESTS)
+    .try_collect()
+    .await
+}
+
+/// Fetch the stats and files of a commit which came without them.
+pub async fn fetch_commit_details(
+    auth: &GitHubAuth,
+    commit: &GitHubCommit,
+) -> Result<GitHubCommit, CreateIssuesError> {
+    fetch_commit(auth, &repo_of_url(&commit.html_url), &commit.sha).await
+}
+
+async fn fetch_commit(
+    auth: &GitHubAuth,
+    repo: &str,
+    sha: &str,
+) -> Result<GitHubCommit, CreateIssuesError> {
+    let client = reqwest::Client::builder().build()?;
+
+    let mut request = client.get(format!(
+        "https://api.github.com/repos/{}/commits/{}",
+        repo, sha
+    ));
+    request = build_request_headers(auth, request, Some(repo), Access::Read).await?;
+
+    Ok(send(request)
+        .await?
+        .error_for_status()?
+        .json::<GitHubCommit>()
+        .await?)
+}
+
+/// Get the sha of the newest commit on a branch.
+pub async fn fetch_branch_head(
+    auth: &GitHubAuth,
+    repo: &str,
+    branch: &str,
+) -> Result<String, CreateIssuesError> {
+    let client = reqwest::Client::builder().build()?;
+
+    let mut request = client.get(format!(
+        "https://api.github.com/repos/{}/branches/{}",
+        repo, branch
+    ));
+    request = build_request_headers(auth, request, Some(repo), Access::Read).await?;
+
+    let branch = send(request)
+        .await?
+        .error_for_status()?
+        .json::<GitHubBranch>()
+        .await?;
+
+    Ok(branch.commit.sha)
+}
+
+pub async fn fetch_compare(
+    auth: &GitHubAuth,
+    repo: &str,
+    base: &str,
+    head: &str,
+) -> Result<GitHubCompare, CreateIssuesError> {
+    let client = reqwest::Client::builder().build()?;
+
+    let mut request = client.get(format!(
+        "https://api.github.com/repos/{}/compare/{}...{}",
+        repo, base, head
+    ));
+    request = build_request_headers(auth, request, Some(repo), Access::Read).await?;
+
+    Ok(send(request)
+        .await?
+        .error_for_status()?
+        .json::<GitHubCompare>()
+        .await?)
+}
+
+/// Generate the issue payloads for the given commits without sending them.
+pub fn build_issues(
+    commits: Vec<GitHubCommit>,
+    template: &IssueTemplate,
+) -> Vec<GithubCreateIssue> {
+    commits
+        .into_iter()
+        .map(|commit| {
+            let changes = ChangeSummary::analyze(&commit.files);
+            let parsed = construct(commit.commit.message);
+            GithubCreateIssue::new(parsed.0, parsed.1, commit.html_url, &changes, template)
+        })
+        .collect()
+}
+
+/// Create a single issue in the given repo.
+pub async fn submit_issue(
+    auth: &GitHubAuth,
+    repo: &str,
+    issue: &GithubCreateIssue,
+) -> Result<GitHubIssue, CreateIssuesError> {
+    let client = reqwest::Client::builder().build()?;
+
+    let mut request = client.post(format!("https://api.github.com/repos/{}/issues", repo));
+
+    request = build_request_headers(auth, request, Some(repo), Access::Write).await?;
+    request = request.header(reqwest::header::ACCEPT, "application/vnd.github.v3+json");
+    let res = send(request.json(issue)).await?.error_for_status()?;
+    METRICS.issues_created.fetch_add(1, Ordering::Relaxed);
+
+    Ok(res.json::<GitHubIssue>().await?)
+}
+
+/// Create the given issues in the given repo.
+pub async fn submit_issues(
+    auth: &GitHubAuth,
+    repo: &str,
+    issues: Vec<GithubCreateIssue>,
+) -> Result<Vec<GitHubIssue>, CreateIssuesError> {
+    let mut created = Vec::with_capacity(issues.len());
+    let mut len = issues.len();
+
+    for issue in issues {
+        created.push(submit_issue(auth, repo, &issue).await?);
+
+        len -= 1;
+        if len != 0 {
+            tokio::time::sleep(ISSUE_CREATION_PAUSE).await;
+        }
+    }
+
+    Ok(created)
+}
+
+/// Close a bot created issue.
+///
+/// When `invalid` is set the issue also gets locked and labeled as `invalid`.
+pub async fn close_issue(
+    auth: &GitHubAuth,
+    repo: &str,
+    number: i64,
+    invalid: bool,
+) -> Result<(), CreateIssuesError> {
+    let client = reqwest::Client::builder().build()?;
+    let issue_url = format!("https://api.github.com/repos/{}/issues/{}", repo, number);
+
+    let mut request = client.patch(&issue_url);
+    request = build_request_headers(auth, request, Some(repo), Access::Write).await?;
+    request = request.header(reqwest::header::ACCEPT, "application/vnd.github.v3+json");
+    send(request.json(&serde_json::json!({ "state": "closed" })))
+        .await?
+        .error_for_status()?;
+
+    if !invalid {
+        return Ok(());
     }
 
+    let mut request = client.post(format!("{}/labels", issue_url));
+    request = build_request_headers(auth, request, Some(repo), Access::Write).await?;
+    request = request.header(reqwest::header::ACCEPT, "application/vnd.github.v3+json");
+    send(request.json(&serde_json::json!({ "labels": ["invalid"] })))
+        .await?
+        .error_for_status()?;
+
+    let mut request = client.put(format!("{}/lock", issue_url));
+    request = build_request_headers(auth, request, Some(repo), Access::Write).await?;
+    request = request.header(reqwest::header::ACCEPT, "application/vnd.github.v3+json");
+    send(request.json(&serde_json::json!({})))
+        .await?
+        .error_for_status()?;
+
     Ok(())
 }
 
-#[derive(Debug, serde::Serialize)]
+/// Comment on a bot created issue.
+pub async fn comment_on_issue(
+    auth: &GitHubAuth,
+    repo: &str,
+    number: i64,
+    body: &str,
+) -> Result<(), CreateIssuesError> {
+    let client = reqwest::Client::builder().build()?;
+
+    let mut request = client.post(format!(
+        "https://api.github.com/repos/{}/issues/{}/comments",
+        repo, number
+    ));
+    request = build_request_headers(auth, request, Some(repo), Access::Write).await?;
+    request = request.header(reqwest::header::ACCEPT, "application/vnd.github.v3+json");
+    send(request.json(&serde_json::json!({ "body": body })))
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+/// Fetch every issue of a repo which changed since the given time, in the ISO 8601 format.
+///
+/// One request covers up to 100 changed issues, no matter how many issues are tracked.
+pub async fn fetch_updated_issues(
+    auth: &GitHubAuth,
+    repo: &str,
+    since: &str,
+) -> Result<Vec<GitHubIssue>, CreateIssuesError> {
+    let client = reqwest::Client::builder().build()?;
+
+    let mut issues = vec![];
+    for page in 1.. {
+        let mut request = client.get(format!(
+            "https://api.github.com/repos/{}/issues?state=all&since={}&per_page=100&page={}",
+            repo, since, page
+        ));
+        request = build_request_headers(auth, request, Some(repo), Access::Read).await?;
+        request = request.header(reqwest::header::ACCEPT, "application/vnd.github.v3+json");
+
+        let batch = send(request)
+            .await?
+            .error_for_status()?
+            .json::<Vec<GitHubIssue>>()
+            .await?;
+        let last_page = batch.len() < 100;
+        issues.extend(batch);
+
+        if last_page {
+            break;
+        }
+    }
+
+    Ok(issues)
+}
+
+/// Find the html url of a merged pull request which references the issue.
+pub async fn fetch_merged_pull_request(
+    auth: &GitHubAuth,
+    repo: &str,
+    number: i64,
+) -> Result<Option<String>, CreateIssuesError> {
+    let client = reqwest::Client::builder().build()?;
+
+    let mut request = client.get(format!(
+        "https://api.github.com/repos/{}/issues/{}/timeline?per_page=100",
+        repo, number
+    ));
+    request = build_request_headers(auth, request, Some(repo), Access::Read).await?;
+    request = request.header(reqwest::header::ACCEPT, "application/vnd.github.v3+json");
+
+    let events = send(request)
+        .await?
+        .error_for_status()?
+        .json::<Vec<TimelineEvent>>()
+        .await?;
+
+    Ok(events
+        .into_iter()
+        .filter(|event| event.event == "cross-referenced")
+        .filter_map(|event| event.source?.issue?.pull_request)
+        .find(|pull_request| pull_request.merged_at.is_some())
+        .map(|pull_request| pull_request.html_url))
+}
+
+/// How the issues of a guild look.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct IssueTemplate {
+    /// Put in front of every title, like `[api-docs]`.
+    pub title_prefix: String,
+    /// Added to every issue, next to the labels of the changes.
+    pub labels: Vec<String>,
+}
+
+impl Default for IssueTemplate {
+    fn default() -> Self {
+        Self {
+            title_prefix: "[api-docs]".into(),
+            labels: vec!["api-docs-commits".into()],
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct GithubCreateIssue {
-    title: String,
-    body: String,
-    labels: Vec<String>,
+    pub title: String,
+    pub body: String,
+    pub labels: Vec<String>,
 }
 
 impl GithubCreateIssue {
-    fn new(title: String, description: Option<String>, url: String) -> Self {
+    fn new(
+        title: String,
+        description: Option<String>,
+        url: String,
+        changes: &ChangeSummary,
+        template: &IssueTemplate,
+    ) -> Self {
+        let mut details = match changes.to_markdown() {
+            Some(details) => format!("\n\n{}", details),
+            None => String::new(),
+        };
+        if let Some(diff) = suggest_type_diff(changes) {
+            details.push_str(&format!("\n\n### Suggested type changes\n{}", diff));
+        }
+
+        let mut labels = template.labels.clone();
+        labels.extend(changes.labels().into_iter().map(String::from));
+
         Self {
-            title: format!("[api-docs] {}", title),
+            title: if template.title_prefix.is_empty() {
+                title
+            } else {
+                format!("{} {}", template.title_prefix, title)
+            },
             body: format!(
-                "A new commit was made into the api-docs repo: {}\n{}\n\nThis is a bot created issue.",
+                "A new commit was made into the api-docs repo: {}\n{}{}\n\nThis is a bot created issue.",
                 url,
                 description.unwrap_or_else(|| "No details given.".to_owned()),
+                details,
             ),
-            labels: vec!["api-docs-commits".into()],
+            labels,
         }
     }
 }
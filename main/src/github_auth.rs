@@ -0,0 +1,361 @@
+use crate::{config::Config, constants::GITHUB_USER_AGENT, github::send};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use ring::{
+    error::KeyRejected,
+    rand::SystemRandom,
+    signature::{RsaKeyPair, RSA_PKCS1_SHA256},
+};
+use serde::Deserialize;
+use std::{collections::HashMap, env, fs, io, path::Path};
+
+/// Installation tokens are refreshed this long before they expire, so none runs out mid-job.
+const REFRESH_MARGIN_SECS: i64 = 300;
+/// How long a repo the app is not installed on is remembered as such, so installing the app
+/// there is noticed without a restart.
+const NOT_INSTALLED_SECS: i64 = 600;
+
+#[derive(Debug, thiserror::Error)]
+pub enum GitHubAuthError {
+    #[error("The environment variable GITHUB_ACCESS_TOKEN or GITHUB_APP_ID needs to be set")]
+    MissingCredentials,
+    #[error("Error while reading the private key of the GitHub App: {0}")]
+    Io(#[from] io::Error),
+    #[error("The private key of the GitHub App is not a PEM file")]
+    InvalidPem,
+    #[error("The private key of the GitHub App was rejected: {0}")]
+    InvalidKey(KeyRejected),
+    #[error("Could not sign the JWT of the GitHub App")]
+    Signing,
+    #[error("The expiry of an installation token could not be read: {0}")]
+    InvalidExpiry(#[from] chrono::ParseError),
+    #[error("The GitHub App is not installed on {0}, it can only read there")]
+    NotInstalled(String),
+    #[error("Error from reqwest: {0}")]
+    Reqwest(#[from] reqwest::Error),
+}
+
+/// Who the bot is on GitHub, and so who the issues and comments are authored by.
+pub enum GitHubAuth {
+    /// A personal access token, everything is done as its owner.
+    Token(String),
+    App(Box<GitHubApp>),
+}
+
+/// What a request does in the repo it concerns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    /// Creating, closing or commenting, which the app may only do where it is installed.
+    Write,
+}
+
+/// A GitHub App with the installations and tokens it found so far.
+pub struct GitHubApp {
+    app_id: u64,
+    key: RsaKeyPair,
+    /// Used for repos the app is not installed on, which are only read.
+    installation_id: u64,
+    /// The installation of every repo seen so far with when it was looked up, `None` if the app
+    /// is not installed on it.
+    installations: Mutex<HashMap<String, (Option<u64>, i64)>>,
+    /// The tokens of the installations with their expiry, keyed by the installation id.
+    tokens: Mutex<HashMap<u64, (String, i64)>>,
+}
+
+#[derive(Deserialize)]
+struct Installation {
+    id: u64,
+}
+
+#[derive(Deserialize)]
+struct AccessToken {
+    token: String,
+    expires_at: String,
+}
+
+impl GitHubAuth {
+    /// Authenticate as the GitHub App of the config, or with the access token if there is none.
+    pub fn from_config(config: &Config) -> Result<Self, GitHubAuthError> {
+        Ok(match &config.github_app {
+            Some(app) => Self::App(Box::new(GitHubApp {
+                app_id: app.app_id,
+                key: load_key(&app.private_key_path)?,
+                installation_id: app.installation_id,
+                installations: Mutex::new(HashMap::new()),
+                tokens: Mutex::new(HashMap::new()),
+            })),
+            None => Self::Token(
+                env::var("GITHUB_ACCESS_TOKEN").map_err(|_| GitHubAuthError::MissingCredentials)?,
+            ),
+        })
+    }
+
+    /// The `Authorization` header for a request concerning a repo, `None` for requests which
+    /// concern no repo in particular.
+    ///
+    /// The app reads repos it is not installed on through its default installation, writes
+    /// there are rejected instead of ending up in another repo's installation.
+    pub async fn authorization(
+        &self,
+        repo: Option<&str>,
+        access: Access,
+    ) -> Result<String, GitHubAuthError> {
+        let app = match self {
+            Self::Token(token) => return Ok(format!("token {}", token)),
+            Self::App(app) => app,
+        };
+
+        let installation_id = match repo {
+            Some(repo) => app.installation_of(repo).await?,
+            None => None,
+        };
+        let installation_id = match (installation_id, access, repo) {
+            (Some(installation_id), _, _) => installation_id,
+            (None, Access::Write, Some(repo)) => {
+                return Err(GitHubAuthError::NotInstalled(repo.to_owned()))
+            }
+            (None, _, _) => app.installation_id,
+        };
+
+        Ok(format!(
+            "token {}",
+            app.installation_token(installation_id).await?
+        ))
+    }
+}
+
+/// Read the private key GitHub generated for the app.
+fn load_key(path: &Path) -> Result<RsaKeyPair, GitHubAuthError> {
+    let pem = fs::read_to_string(path)?;
+    let der = base64::decode(
+        pem.lines()
+            .filter(|line| !line.starts_with("-----"))
+            .collect::<String>(),
+    )
+    .map_err(|_| GitHubAuthError::InvalidPem)?;
+
+    // GitHub hands out PKCS#1 keys, converted keys are PKCS#8
+    if pem.contains("BEGIN RSA PRIVATE KEY") {
+        RsaKeyPair::from_der(&der)
+    } else {
+        RsaKeyPair::from_pkcs8(&der)
+    }
+    .map_err(GitHubAuthError::InvalidKey)
+}
+
+/// Whether a cached installation can still be used, installations are not removed but repos
+/// without one are looked up again after a while.
+fn is_current(installation_id: Option<u64>, found_at: i64, now: i64) -> bool {
+    installation_id.is_some() || now - found_at < NOT_INSTALLED_SECS
+}
+
+impl GitHubApp {
+    /// A JWT which authenticates as the app itself, only needed to get installation tokens.
+    fn jwt(&self) -> Result<String, GitHubAuthError> {
+        let encode = |data: &[u8]| base64::encode_config(data, base64::URL_SAFE_NO_PAD);
+
+        // Backdated against clock drift, GitHub rejects JWTs valid for more than 10 minutes
+        let now = Utc::now().timestamp();
+        let claims = serde_json::json!({
+            "iat": now - 60,
+            "exp": now + 540,
+            "iss": self.app_id.to_string(),
+        });
+        let message = format!(
+            "{}.{}",
+            encode(br#"{"alg":"RS256","typ":"JWT"}"#),
+            encode(claims.to_string().as_bytes())
+        );
+
+        let mut signature = vec![0; self.key.public_modulus_len()];
+        self.key
+            .sign(
+                &RSA_PKCS1_SHA256,
+                &SystemRandom::new(),
+                message.as_bytes(),
+                &mut signature,
+            )
+            .map_err(|_| GitHubAuthError::Signing)?;
+
+        Ok(format!("{}.{}", message, encode(&signature)))
+    }
+
+    /// Send a request as the app itself.
+    async fn send_as_app(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, GitHubAuthError> {
+        let request = request
+            .header(reqwest::header::USER_AGENT, GITHUB_USER_AGENT)
+            .header(
+                reqwest::header::AUTHORIZATION,
+                format!("Bearer {}", self.jwt()?),
+            )
+            .header(reqwest::header::ACCEPT, "application/vnd.github.v3+json");
+
+        Ok(send(request).await?)
+    }
+
+    async fn installation_of(&self, repo: &str) -> Result<Option<u64>, GitHubAuthError> {
+        let now = Utc::now().timestamp();
+        let cached = self
+            .installations
+            .lock()
+            .get(repo)
+            .filter(|(installation_id, found_at)| is_current(*installation_id, *found_at, now))
+            .map(|(installation_id, _)| *installation_id);
+        if let Some(installation_id) = cached {
+            return Ok(installation_id);
+        }
+
+        let client = reqwest::Client::builder().build()?;
+        let res = self
+            .send_as_app(client.get(format!(
+                "https://api.github.com/repos/{}/installation",
+                repo
+            )))
+            .await?;
+
+        let installation_id = if res.status() == reqwest::StatusCode::NOT_FOUND {
+            None
+        } else {
+            Some(res.error_for_status()?.json::<Installation>().await?.id)
+        };
+        tracing::debug!(repo, installation = ?installation_id, "Found the installation of a repo");
+
+        self.installations
+            .lock()
+            .insert(repo.to_owned(), (installation_id, now));
+
+        Ok(installation_id)
+    }
+
+    /// A token of the installation, a new one is requested shortly before the old one expires.
+    async fn installation_token(&self, installation_id: u64) -> Result<String, GitHubAuthError> {
+        let now = Utc::now().timestamp();
+        let cached = self
+            .tokens
+            .lock()
+            .get(&installation_id)
+            .filter(|(_, expires_at)| *expires_at - REFRESH_MARGIN_SECS > now)
+            .map(|(token, _)| token.clone());
+        if let Some(token) = cached {
+            return Ok(token);
+        }
+
+        let client = reqwest::Client::builder().build()?;
+        let created = self
+            .send_as_app(client.post(format!(
+                "https://api.github.com/app/installations/{}/access_tokens",
+                installation_id
+            )))
+            .await?
+            .error_for_status()?
+            .json::<AccessToken>()
+            .await?;
+        let expires_at = DateTime::parse_from_rfc3339(&created.expires_at)?.timestamp();
+        tracing::info!(
+            installation = installation_id,
+            expires_at,
+            "Got a new installation token"
+        );
+
+        self.tokens
+            .lock()
+            .insert(installation_id, (created.token.clone(), expires_at));
+
+        Ok(created.token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::signature::{KeyPair, UnparsedPublicKey, RSA_PKCS1_2048_8192_SHA256};
+    use serde_json::Value;
+
+    /// A key which was only generated for these tests.
+    fn app() -> GitHubApp {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/github-app.pem");
+
+        GitHubApp {
+            app_id: 123456,
+            key: load_key(&path).unwrap(),
+            installation_id: 1,
+            installations: Mutex::new(HashMap::new()),
+            tokens: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn decode(part: &str) -> Value {
+        serde_json::from_slice(&base64::decode_config(part, base64::URL_SAFE_NO_PAD).unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn jwt_has_an_rs256_header() {
+        let jwt = app().jwt().unwrap();
+        let header = jwt.split('.').next().unwrap();
+
+        assert_eq!(
+            decode(header),
+            serde_json::json!({ "alg": "RS256", "typ": "JWT" })
+        );
+    }
+
+    #[test]
+    fn jwt_claims_fit_into_the_ten_minutes_github_allows() {
+        let now = Utc::now().timestamp();
+        let jwt = app().jwt().unwrap();
+        let claims = decode(jwt.split('.').nth(1).unwrap());
+
+        // GitHub wants the app id as the issuer, as a string
+        assert_eq!(claims["iss"], "123456");
+        let issued_at = claims["iat"].as_i64().unwrap();
+        let expires_at = claims["exp"].as_i64().unwrap();
+        assert!(issued_at < now && issued_at >= now - 120);
+        assert!(expires_at > now && expires_at - issued_at <= 600);
+    }
+
+    #[test]
+    fn jwt_is_signed_with_the_key() {
+        let app = app();
+        let jwt = app.jwt().unwrap();
+        let (message, signature) = jwt.rsplit_once('.').unwrap();
+        let signature = base64::decode_config(signature, base64::URL_SAFE_NO_PAD).unwrap();
+
+        let public_key =
+            UnparsedPublicKey::new(&RSA_PKCS1_2048_8192_SHA256, app.key.public_key().as_ref());
+        assert!(public_key.verify(message.as_bytes(), &signature).is_ok());
+        assert!(public_key
+            .verify(format!("{}x", message).as_bytes(), &signature)
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_files_which_are_no_pem() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+
+        assert!(matches!(load_key(&path), Err(GitHubAuthError::InvalidPem)));
+    }
+
+    #[test]
+    fn rejects_pems_which_hold_no_key() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/not-a-key.pem");
+
+        assert!(matches!(
+            load_key(&path),
+            Err(GitHubAuthError::InvalidKey(_))
+        ));
+    }
+
+    #[test]
+    fn repos_without_the_app_are_looked_up_again() {
+        let now = Utc::now().timestamp();
+
+        assert!(is_current(Some(7), now - 86400, now));
+        assert!(is_current(None, now - 60, now));
+        assert!(!is_current(None, now - NOT_INSTALLED_SECS, now));
+    }
+}
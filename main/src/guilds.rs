@@ -0,0 +1,125 @@
+use crate::{
+    config::Config,
+    constants::{API_DOCS_BOT_ID, API_DOCS_CHANNEL, DEFAULT_ISSUE_REPO, ISSUE_MANAGEMENT_USERS},
+    database::{Data, GuildSettings},
+    github::IssueTemplate,
+    metrics::Recorded,
+    routes::default_routes,
+    types::State,
+};
+use std::error::Error;
+use twilight_model::id::{
+    marker::{ChannelMarker, GuildMarker},
+    Id,
+};
+
+/// The settings of guilds which were never set up, this is how the bot always behaved.
+pub fn default_settings(config: &Config) -> GuildSettings {
+    GuildSettings {
+        relay_channel: Some(API_DOCS_CHANNEL.get()),
+        webhook_user: Some(API_DOCS_BOT_ID.get()),
+        issue_repo: DEFAULT_ISSUE_REPO.into(),
+        managers: ISSUE_MANAGEMENT_USERS.iter().map(|id| id.get()).collect(),
+        routes: config.routes.clone(),
+        template: IssueTemplate::default(),
+    }
+}
+
+/// What a guild starts out with when it is set up, nothing is relayed until it has a channel
+/// and no issues are created until it chose a repo.
+pub fn empty_settings() -> GuildSettings {
+    GuildSettings {
+        relay_channel: None,
+        webhook_user: None,
+        issue_repo: String::new(),
+        managers: vec![],
+        routes: default_routes(),
+        template: IssueTemplate::default(),
+    }
+}
+
+/// The guild of the API docs channel, it is looked up once and then remembered.
+pub async fn api_docs_guild(
+    state: &State,
+) -> Result<Option<Id<GuildMarker>>, Box<dyn Error + Send + Sync>> {
+    if let Some(guild_id) = state.database.read(|data| data.api_docs_guild) {
+        return Ok(Id::new_checked(guild_id));
+    }
+
+    let channel = state
+        .http
+        .channel(API_DOCS_CHANNEL)
+        .exec()
+        .recorded()
+        .await?
+        .model()
+        .await?;
+    if let Some(guild_id) = channel.guild_id {
+        state
+            .database
+            .write(|data| data.api_docs_guild = Some(guild_id.get()))?;
+    }
+
+    Ok(channel.guild_id)
+}
+
+/// What a guild starts out with on its first `/apidocs settings`, the guild of the API docs
+/// channel keeps how the bot always behaved there.
+pub async fn initial_settings(state: &State, guild_id: Id<GuildMarker>) -> GuildSettings {
+    match api_docs_guild(state).await {
+        Ok(Some(api_docs_guild)) if api_docs_guild == guild_id => default_settings(&state.config),
+        _ => empty_settings(),
+    }
+}
+
+/// The settings of a guild, guilds which were never set up get the empty settings.
+///
+/// Only the guild of the API docs channel gets the default settings, once it is known.
+pub fn settings_of_guild(state: &State, guild_id: Option<Id<GuildMarker>>) -> GuildSettings {
+    let guild_id = match guild_id {
+        Some(guild_id) => guild_id.get(),
+        None => return empty_settings(),
+    };
+
+    state
+        .database
+        .read(|data| saved_settings(data, &state.config, guild_id))
+}
+
+/// The settings of a guild in the data, see [`settings_of_guild`].
+pub fn saved_settings(data: &Data, config: &Config, guild_id: u64) -> GuildSettings {
+    match data.guilds.get(&guild_id) {
+        Some(settings) => settings.clone(),
+        None if data.api_docs_guild == Some(guild_id) => default_settings(config),
+        None => empty_settings(),
+    }
+}
+
+/// The settings of the guild which relays into a channel, `None` if nothing is relayed there.
+pub fn settings_of_channel(state: &State, channel_id: Id<ChannelMarker>) -> Option<GuildSettings> {
+    relay_targets(state)
+        .into_iter()
+        .find(|settings| settings.relay_channel == Some(channel_id.get()))
+}
+
+/// The settings of every guild pushes are relayed to.
+///
+/// The API docs channel keeps getting relays until a guild which was set up takes it over.
+pub fn relay_targets(state: &State) -> Vec<GuildSettings> {
+    let mut targets: Vec<GuildSettings> = state.database.read(|data| {
+        data.guilds
+            .values()
+            .filter(|settings| settings.relay_channel.is_some())
+            .cloned()
+            .collect()
+    });
+
+    if !targets
+        .iter()
+        .any(|settings| settings.relay_channel == Some(API_DOCS_CHANNEL.get()))
+    {
+        targets.push(default_settings(&state.config));
+    }
+
+    targets
+}
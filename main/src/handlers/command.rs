@@ -0,0 +1,1269 @@
+use super::{
+    dry_run_followup, ephemeral_response, is_issue_manager, mirror, HandlerResult, NO_ISSUE_REPO,
+};
+use crate::{
+    audit,
+    constants::{API_DOCS_REPO, APPLICATION_ID, PUSH_EMBED_COLOR},
+    database::{GuildSettings, HistoryEvent, IssueStatus},
+    dry_run::DryRunAction,
+    embeds::{report_embeds, split_embeds},
+    github::{check_token, close_issue},
+    guilds::{initial_settings, settings_of_guild},
+    metrics::{Recorded, METRICS},
+    process::ProcessStats,
+    replay::{replay, ReplaySource},
+    report::diff_docs,
+    routes::Route,
+    types::State,
+};
+use chrono::{Duration, NaiveDate, TimeZone, Utc};
+use std::{
+    collections::HashMap,
+    error::Error,
+    sync::{atomic::Ordering, Arc},
+};
+use twilight_model::{
+    application::{
+        command::{Command, CommandType},
+        interaction::{
+            application_command::{CommandDataOption, CommandOptionValue},
+            ApplicationCommand,
+        },
+    },
+    channel::{embed::Embed, message::MessageFlags},
+    guild::Permissions,
+    http::{
+        attachment::Attachment,
+        interaction::{InteractionResponse, InteractionResponseData, InteractionResponseType},
+    },
+    id::Id,
+};
+use twilight_util::builder::command::{
+    BooleanBuilder, ChannelBuilder, CommandBuilder, IntegerBuilder, StringBuilder,
+    SubCommandBuilder, UserBuilder,
+};
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder};
+
+/// How many audit entries `/apidocs audit` shows, newest first.
+const AUDIT_PAGE_SIZE: usize = 20;
+
+/// All application commands the bot registers on startup.
+pub fn commands() -> Vec<Command> {
+    vec![
+        CommandBuilder::new(
+            "apidocs".into(),
+            "Manage the Discord API docs issues".into(),
+            CommandType::ChatInput,
+        )
+        .option(
+            SubCommandBuilder::new("close".into(), "Close an issue created by this bot".into())
+                .option(
+                    IntegerBuilder::new("issue".into(), "The number of the issue".into())
+                        .required(true),
+                )
+                .option(BooleanBuilder::new(
+                    "invalid".into(),
+                    "Also lock the issue and label it as invalid".into(),
+                )),
+        )
+        .option(
+            SubCommandBuilder::new(
+                "diff".into(),
+                "Report what changed in the docs between two refs".into(),
+            )
+            .option(
+                StringBuilder::new("from".into(), "The older ref, like a commit or tag".into())
+                    .required(true),
+            )
+            .option(
+                StringBuilder::new("to".into(), "The newer ref, like `master`".into())
+                    .required(true),
+            )
+            .option(BooleanBuilder::new(
+                "attachment".into(),
+                "Also attach the full report as a Markdown file".into(),
+            )),
+        )
+        .option(SubCommandBuilder::new(
+            "status".into(),
+            "Show how the bot is doing".into(),
+        ))
+        .option(
+            SubCommandBuilder::new(
+                "audit".into(),
+                "Show who took which privileged actions".into(),
+            )
+            .option(UserBuilder::new(
+                "user".into(),
+                "Only the actions of this user".into(),
+            ))
+            .option(StringBuilder::new(
+                "since".into(),
+                "Like `12h`, `7d`, `2w` or `2022-05-01`".into(),
+            )),
+        )
+        .option(
+            SubCommandBuilder::new(
+                "replay".into(),
+                "Relay pushes of the webhook in this channel again".into(),
+            )
+            .option(
+                StringBuilder::new("message".into(), "The id of the (first) message".into())
+                    .required(true),
+            )
+            .option(StringBuilder::new(
+                "until".into(),
+                "The id of the last message, to replay a range".into(),
+            ))
+            .option(BooleanBuilder::new(
+                "dry_run".into(),
+                "Only show what would be relayed".into(),
+            )),
+        )
+        .option(
+            SubCommandBuilder::new(
+                "backfill".into(),
+                "Relay the commits between two SHAs as one push".into(),
+            )
+            .option(
+                StringBuilder::new("base".into(), "The last commit which was relayed".into())
+                    .required(true),
+            )
+            .option(
+                StringBuilder::new("head".into(), "The last commit to relay".into()).required(true),
+            )
+            .option(StringBuilder::new(
+                "repo".into(),
+                "Like `discord/discord-api-docs`, which is the default".into(),
+            ))
+            .option(StringBuilder::new(
+                "branch".into(),
+                "The branch the commits were pushed to, `master` by default".into(),
+            ))
+            .option(BooleanBuilder::new(
+                "dry_run".into(),
+                "Only show what would be relayed".into(),
+            )),
+        )
+        .option(
+            SubCommandBuilder::new(
+                "settings".into(),
+                "Show or change how the bot works in this server".into(),
+            )
+            .option(ChannelBuilder::new(
+                "channel".into(),
+                "Where pushes are relayed".into(),
+            ))
+            .option(StringBuilder::new(
+                "webhook".into(),
+                "The id of the GitHub webhook posting into that channel".into(),
+            ))
+            .option(StringBuilder::new(
+                "repo".into(),
+                "Where issues are created, like `discordeno/discordeno`".into(),
+            ))
+            .option(StringBuilder::new(
+                "routes".into(),
+                "Which pushes are relayed, as JSON like in `routes.example.json`".into(),
+            ))
+            .option(StringBuilder::new(
+                "title_prefix".into(),
+                "Put in front of every issue title, `none` for nothing".into(),
+            ))
+            .option(StringBuilder::new(
+                "labels".into(),
+                "Comma separated labels of every issue, `none` for no labels".into(),
+            ))
+            .option(UserBuilder::new(
+                "add_manager".into(),
+                "Let this user create, close and comment on issues".into(),
+            ))
+            .option(UserBuilder::new(
+                "remove_manager".into(),
+                "Take that away from this user again".into(),
+            )),
+        )
+        .build(),
+        CommandBuilder::new("Send to issue".into(), String::new(), CommandType::Message).build(),
+    ]
+}
+
+pub(super) async fn handle_command(
+    command: Box<ApplicationCommand>,
+    state: State,
+) -> HandlerResult {
+    if command.data.name != "apidocs" && command.data.name != "Send to issue" {
+        return Ok(());
+    }
+
+    let http = state.http.interaction(APPLICATION_ID);
+
+    // Guild admins set up the bot, they do not need to be managers for that
+    if let Some(CommandDataOption {
+        name,
+        value: CommandOptionValue::SubCommand(options),
+        ..
+    }) = command.data.options.first()
+    {
+        if name == "settings" {
+            return guild_settings(&command, options, state).await;
+        }
+    }
+
+    let settings = settings_of_guild(&state, command.guild_id);
+
+    if !is_issue_manager(&settings, command.author_id()) {
+        http.create_response(
+            command.id,
+            &command.token,
+            &ephemeral_response("You do not have access to this."),
+        )
+        .exec()
+        .recorded()
+        .await?;
+
+        return Ok(());
+    }
+
+    if command.data.name == "Send to issue" {
+        let target = command.data.target_id.and_then(|target_id| {
+            command
+                .data
+                .resolved
+                .as_ref()?
+                .messages
+                .get(&target_id.cast())
+        });
+        let entry = audit::entry(
+            command.guild_id,
+            command.author_id(),
+            "Send to issue",
+            target,
+        );
+
+        let result = mirror::send_to_issue(&command, Arc::clone(&state)).await;
+        audit::record(&state, entry, &result).await;
+
+        return result;
+    }
+
+    let (name, options) = match command.data.options.first() {
+        Some(CommandDataOption {
+            name,
+            value: CommandOptionValue::SubCommand(options),
+            ..
+        }) => (name.as_str(), options),
+        _ => return Ok(()),
+    };
+
+    match name {
+        "close" => {
+            let number = options.iter().find_map(|option| match option.value {
+                CommandOptionValue::Integer(number) if option.name == "issue" => Some(number),
+                _ => None,
+            });
+            let mut entry = audit::entry(
+                command.guild_id,
+                command.author_id(),
+                &format!("/apidocs close #{}", number.unwrap_or_default()),
+                None,
+            );
+            entry.message_id = number.and_then(|number| {
+                state
+                    .database
+                    .read(|data| data.message_of_issue(&settings, number))
+            });
+
+            let result = close(&command, options, &settings, Arc::clone(&state)).await;
+            audit::record(&state, entry, &result).await;
+
+            result
+        }
+        "diff" => diff(&command, options, state).await,
+        "status" => status(&command, &settings, state).await,
+        "audit" => audit_log(&command, options, state).await,
+        "replay" | "backfill" => {
+            let (source, dry_run) = match replay_source(name, options) {
+                Some(parsed) => parsed,
+                None => {
+                    http.create_response(
+                        command.id,
+                        &command.token,
+                        &ephemeral_response("`message` and `until` need to be message ids."),
+                    )
+                    .exec()
+                    .recorded()
+                    .await?;
+
+                    return Ok(());
+                }
+            };
+
+            // Dry runs change nothing, so there is nothing to audit
+            if dry_run || state.config.dry_run {
+                return replay_pushes(&command, &settings, &source, true, state).await;
+            }
+
+            let mut entry = audit::entry(
+                command.guild_id,
+                command.author_id(),
+                &format!("/apidocs {}", name),
+                None,
+            );
+            match &source {
+                ReplaySource::Messages { first, .. } => entry.message_id = Some(first.get()),
+                ReplaySource::Commits {
+                    repo, base, head, ..
+                } => {
+                    entry.upstream = Some(format!(
+                        "https://github.com/{}/compare/{}...{}",
+                        repo, base, head
+                    ))
+                }
+            }
+
+            let result =
+                replay_pushes(&command, &settings, &source, false, Arc::clone(&state)).await;
+            audit::record(&state, entry, &result).await;
+
+            result
+        }
+        _ => Ok(()),
+    }
+}
+
+async fn close(
+    command: &ApplicationCommand,
+    options: &[CommandDataOption],
+    settings: &GuildSettings,
+    state: State,
+) -> HandlerResult {
+    let http = state.http.interaction(APPLICATION_ID);
+
+    let mut number = None;
+    let mut invalid = false;
+    for option in options {
+        match (option.name.as_str(), &option.value) {
+            ("issue", CommandOptionValue::Integer(value)) => number = Some(*value),
+            ("invalid", CommandOptionValue::Boolean(value)) => invalid = *value,
+            _ => {}
+        }
+    }
+
+    let number = match number {
+        Some(number) => number,
+        None => return Ok(()),
+    };
+
+    if settings.issue_repo.is_empty() {
+        http.create_response(
+            command.id,
+            &command.token,
+            &ephemeral_response(NO_ISSUE_REPO),
+        )
+        .exec()
+        .recorded()
+        .await?;
+
+        return Ok(());
+    }
+
+    let message_id = match state
+        .database
+        .read(|data| data.message_of_issue(settings, number))
+    {
+        Some(message_id) => message_id,
+        None => {
+            http.create_response(
+                command.id,
+                &command.token,
+                &ephemeral_response(&format!("Issue #{} was not created by this bot.", number)),
+            )
+            .exec()
+            .recorded()
+            .await?;
+
+            return Ok(());
+        }
+    };
+    let repo = &settings.issue_repo;
+
+    http.create_response(
+        command.id,
+        &command.token,
+        &InteractionResponse {
+            kind: InteractionResponseType::DeferredChannelMessageWithSource,
+            data: Some(InteractionResponseData {
+                flags: Some(MessageFlags::EPHEMERAL),
+                ..Default::default()
+            }),
+        },
+    )
+    .exec()
+    .recorded()
+    .await?;
+
+    if state.config.dry_run {
+        let action = DryRunAction::CloseIssue {
+            repo: repo.clone(),
+            number,
+            invalid,
+        };
+
+        return dry_run_followup(&state, &command.token, vec![action]).await;
+    }
+
+    close_issue(&state.github, repo, number, invalid).await?;
+
+    state.database.write(|data| {
+        if let Some(created) = data.created_issues.get_mut(&message_id) {
+            for issue in created
+                .issues
+                .iter_mut()
+                .filter(|issue| issue.number == number)
+            {
+                issue.closed = true;
+                issue.status = IssueStatus::Closed;
+            }
+        }
+        data.log(HistoryEvent::IssueClosed {
+            repo: repo.clone(),
+            number,
+        });
+    })?;
+
+    http.update_response(&command.token)
+        .content(Some(&format!("Closed issue #{}.", number)))?
+        .exec()
+        .recorded()
+        .await?;
+
+    Ok(())
+}
+
+async fn diff(
+    command: &ApplicationCommand,
+    options: &[CommandDataOption],
+    state: State,
+) -> HandlerResult {
+    let http = state.http.interaction(APPLICATION_ID);
+
+    let mut from = None;
+    let mut to = None;
+    let mut attach = false;
+    for option in options {
+        match (option.name.as_str(), &option.value) {
+            ("from", CommandOptionValue::String(value)) => from = Some(value.as_str()),
+            ("to", CommandOptionValue::String(value)) => to = Some(value.as_str()),
+            ("attachment", CommandOptionValue::Boolean(value)) => attach = *value,
+            _ => {}
+        }
+    }
+
+    let (from, to) = match (from, to) {
+        (Some(from), Some(to)) => (from, to),
+        _ => return Ok(()),
+    };
+
+    // Large compares take a while to fetch
+    http.create_response(
+        command.id,
+        &command.token,
+        &InteractionResponse {
+            kind: InteractionResponseType::DeferredChannelMessageWithSource,
+            data: None,
+        },
+    )
+    .exec()
+    .recorded()
+    .await?;
+
+    let report = match diff_docs(&state.github, from, to).await {
+        Ok(report) => report,
+        Err(err) => {
+            http.update_response(&command.token)
+                .content(Some(&format!(
+                    "Could not compare `{}` with `{}`: {}",
+                    from, to, err
+                )))?
+                .exec()
+                .recorded()
+                .await?;
+
+            return Ok(());
+        }
+    };
+
+    let attachments = if attach {
+        vec![Attachment::from_bytes(
+            "docs-diff.md".into(),
+            report.to_markdown().into_bytes(),
+            0,
+        )]
+    } else {
+        vec![]
+    };
+
+    let mut messages = split_embeds(report_embeds(&report)?).into_iter();
+
+    http.update_response(&command.token)
+        .embeds(messages.next().as_deref())?
+        .attachments(&attachments)?
+        .exec()
+        .recorded()
+        .await?;
+
+    for embeds in messages {
+        http.create_followup(&command.token)
+            .embeds(&embeds)?
+            .exec()
+            .recorded()
+            .await?;
+    }
+
+    Ok(())
+}
+
+async fn status(
+    command: &ApplicationCommand,
+    settings: &GuildSettings,
+    state: State,
+) -> HandlerResult {
+    let http = state.http.interaction(APPLICATION_ID);
+
+    // Checking the token and reading the process stats can take a moment
+    http.create_response(
+        command.id,
+        &command.token,
+        &InteractionResponse {
+            kind: InteractionResponseType::DeferredChannelMessageWithSource,
+            data: Some(InteractionResponseData {
+                flags: Some(MessageFlags::EPHEMERAL),
+                ..Default::default()
+            }),
+        },
+    )
+    .exec()
+    .recorded()
+    .await?;
+
+    let embed = status_embed(&state, &settings.routes).await?;
+
+    http.update_response(&command.token)
+        .embeds(Some(&[embed]))?
+        .exec()
+        .recorded()
+        .await?;
+
+    Ok(())
+}
+
+async fn status_embed(
+    state: &State,
+    routes: &[Route],
+) -> Result<Embed, Box<dyn Error + Send + Sync>> {
+    let uptime = state.started_at.elapsed().as_secs();
+    let uptime = format!(
+        "{}d {}h {}m",
+        uptime / 86400,
+        uptime % 86400 / 3600,
+        uptime % 3600 / 60
+    );
+
+    let mut shards: Vec<_> = state
+        .cluster
+        .iter()
+        .flat_map(|cluster| cluster.info())
+        .collect();
+    shards.sort_by_key(|(id, _)| *id);
+    let shards = shards
+        .iter()
+        .map(|(id, info)| match info.latency().average() {
+            Some(latency) => format!("Shard {}: {}, {} ms", id, info.stage(), latency.as_millis()),
+            None => format!("Shard {}: {}", id, info.stage()),
+        })
+        .collect::<Vec<_>>();
+
+    let activity = format!(
+        "{} events, {} relays, {} issues created",
+        METRICS.events_received.load(Ordering::Relaxed),
+        METRICS.relays.load(Ordering::Relaxed),
+        METRICS.issues_created.load(Ordering::Relaxed)
+    );
+
+    let (queued, scheduled, last_seen) = state.database.read(|data| {
+        (
+            data.jobs.len(),
+            data.scheduled.len(),
+            data.last_seen.clone(),
+        )
+    });
+    let jobs = format!(
+        "{} running, {} unfinished, {} scheduled",
+        state.running_jobs.load(Ordering::Relaxed),
+        queued,
+        scheduled
+    );
+
+    // The webhook receiver and the upstream watcher remember the newest commit of every branch
+    let routes = routes
+        .iter()
+        .map(|route| {
+            let mut branches: Vec<_> = last_seen
+                .iter()
+                .filter_map(|(key, sha)| {
+                    let branch = key.strip_prefix(&route.repo)?.strip_prefix(':')?;
+                    Some(format!("`{}` {}", branch, sha.get(..7).unwrap_or(sha)))
+                })
+                .collect();
+            branches.sort();
+
+            if branches.is_empty() {
+                format!("**{}**: nothing seen yet", route.name)
+            } else {
+                format!("**{}**: {}", route.name, branches.join(", "))
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let github = match check_token(&state.github).await {
+        Ok(true) => match METRICS.github_rate_limit() {
+            Some(remaining) => format!("Token valid, {} requests left", remaining),
+            None => "Token valid".into(),
+        },
+        Ok(false) => "**Token rejected**".into(),
+        Err(err) => format!("Unreachable: {}", err),
+    };
+
+    let process = match ProcessStats::get() {
+        Ok(stats) => format!(
+            "{:.1} MiB memory, {:.1}s CPU time",
+            stats.resident_memory_bytes as f64 / (1024.0 * 1024.0),
+            stats.cpu_time.as_secs_f64()
+        ),
+        Err(err) => format!("Unavailable: {}", err),
+    };
+
+    let list_or_none = |lines: Vec<String>| -> String {
+        if lines.is_empty() {
+            "None".into()
+        } else {
+            lines.join("\n").chars().take(1024).collect()
+        }
+    };
+
+    Ok(EmbedBuilder::new()
+        .title("Status")
+        .color(PUSH_EMBED_COLOR)
+        .field(EmbedFieldBuilder::new("Uptime", uptime).inline())
+        .field(EmbedFieldBuilder::new("Since start", activity).inline())
+        .field(EmbedFieldBuilder::new("Issue jobs", jobs).inline())
+        .field(EmbedFieldBuilder::new("Shards", list_or_none(shards)))
+        .field(EmbedFieldBuilder::new(
+            "Last seen commits",
+            list_or_none(routes),
+        ))
+        .field(EmbedFieldBuilder::new("GitHub", github).inline())
+        .field(EmbedFieldBuilder::new("Process", process).inline())
+        .validate()?
+        .build())
+}
+
+/// Join lines into an embed description, cut at a whole line to stay within the limit.
+fn description_of(lines: impl IntoIterator<Item = String>) -> String {
+    let mut description = String::new();
+    for line in lines {
+        if description.chars().count() + line.chars().count() + 1 > 4096 {
+            break;
+        }
+        description.push_str(&line);
+        description.push('\n');
+    }
+
+    description
+}
+
+/// Parse a relative age like `7d` or a date like `2022-05-01` into a unix timestamp.
+fn parse_since(since: &str) -> Option<i64> {
+    if let Ok(date) = NaiveDate::parse_from_str(since, "%Y-%m-%d") {
+        return Some(
+            Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0)?)
+                .timestamp(),
+        );
+    }
+
+    // The unit could be any character, so the input can not be split at a byte offset.
+    // Amounts are `u32` so no age is negative or too large for a `Duration`.
+    let age = if let Some(amount) = since.strip_suffix('h') {
+        Duration::hours(amount.parse::<u32>().ok()?.into())
+    } else if let Some(amount) = since.strip_suffix('d') {
+        Duration::days(amount.parse::<u32>().ok()?.into())
+    } else if let Some(amount) = since.strip_suffix('w') {
+        Duration::weeks(amount.parse::<u32>().ok()?.into())
+    } else {
+        return None;
+    };
+
+    Some(Utc::now().checked_sub_signed(age)?.timestamp())
+}
+
+async fn audit_log(
+    command: &ApplicationCommand,
+    options: &[CommandDataOption],
+    state: State,
+) -> HandlerResult {
+    let http = state.http.interaction(APPLICATION_ID);
+
+    let mut user = None;
+    let mut since = None;
+    for option in options {
+        match (option.name.as_str(), &option.value) {
+            ("user", CommandOptionValue::User(value)) => user = Some(value.get()),
+            ("since", CommandOptionValue::String(value)) => since = Some(value.as_str()),
+            _ => {}
+        }
+    }
+
+    let since = match since.map(parse_since) {
+        Some(Some(since)) => Some(since),
+        Some(None) => {
+            http.create_response(
+                command.id,
+                &command.token,
+                &ephemeral_response("`since` must look like `12h`, `7d`, `2w` or `2022-05-01`."),
+            )
+            .exec()
+            .recorded()
+            .await?;
+
+            return Ok(());
+        }
+        None => None,
+    };
+
+    // Guilds only see their own actions
+    let guild_id = command.guild_id.map(Id::get);
+    let lines: Vec<String> = state.database.read(|data| {
+        data.audit
+            .iter()
+            .rev()
+            .filter(|entry| guild_id.is_some() && entry.guild_id == guild_id)
+            .filter(|entry| user.is_none() || user == Some(entry.actor))
+            .filter(|entry| since.is_none() || since <= Some(entry.at))
+            .take(AUDIT_PAGE_SIZE)
+            .map(audit::describe)
+            .collect()
+    });
+
+    let description = if lines.is_empty() {
+        "No matching actions.".into()
+    } else {
+        description_of(lines)
+    };
+
+    let embed = EmbedBuilder::new()
+        .title("Audit log")
+        .color(PUSH_EMBED_COLOR)
+        .description(description)
+        .validate()?
+        .build();
+
+    http.create_response(
+        command.id,
+        &command.token,
+        &InteractionResponse {
+            kind: InteractionResponseType::ChannelMessageWithSource,
+            data: Some(InteractionResponseData {
+                embeds: Some(vec![embed]),
+                flags: Some(MessageFlags::EPHEMERAL),
+                ..Default::default()
+            }),
+        },
+    )
+    .exec()
+    .recorded()
+    .await?;
+
+    Ok(())
+}
+
+/// Read what `/apidocs replay` or `/apidocs backfill` should relay, and whether it is a dry run.
+fn replay_source(name: &str, options: &[CommandDataOption]) -> Option<(ReplaySource, bool)> {
+    let mut values = HashMap::new();
+    let mut dry_run = false;
+    for option in options {
+        match (option.name.as_str(), &option.value) {
+            ("dry_run", CommandOptionValue::Boolean(value)) => dry_run = *value,
+            (name, CommandOptionValue::String(value)) => {
+                values.insert(name, value.as_str());
+            }
+            _ => {}
+        }
+    }
+
+    let source = if name == "replay" {
+        let first = Id::new_checked(values.get("message")?.parse().ok()?)?;
+        let last = match values.get("until") {
+            Some(until) => Id::new_checked(until.parse().ok()?)?,
+            None => first,
+        };
+
+        ReplaySource::Messages { first, last }
+    } else {
+        ReplaySource::Commits {
+            repo: values.get("repo").unwrap_or(&API_DOCS_REPO).to_string(),
+            branch: values.get("branch").unwrap_or(&"master").to_string(),
+            base: values.get("base")?.to_string(),
+            head: values.get("head")?.to_string(),
+        }
+    };
+
+    Some((source, dry_run))
+}
+
+async fn replay_pushes(
+    command: &ApplicationCommand,
+    settings: &GuildSettings,
+    source: &ReplaySource,
+    dry_run: bool,
+    state: State,
+) -> HandlerResult {
+    let http = state.http.interaction(APPLICATION_ID);
+
+    // Ranges and compares take a while to go through
+    http.create_response(
+        command.id,
+        &command.token,
+        &InteractionResponse {
+            kind: InteractionResponseType::DeferredChannelMessageWithSource,
+            data: Some(InteractionResponseData {
+                flags: Some(MessageFlags::EPHEMERAL),
+                ..Default::default()
+            }),
+        },
+    )
+    .exec()
+    .recorded()
+    .await?;
+
+    let pushes = match replay(&state, settings, source, dry_run).await {
+        Ok(pushes) => pushes,
+        Err(err) => {
+            http.update_response(&command.token)
+                .content(Some(&format!("Could not replay: {}", err)))?
+                .exec()
+                .recorded()
+                .await?;
+
+            return Err(err);
+        }
+    };
+
+    let summary = if dry_run {
+        format!("{} pushes found, nothing was relayed.", pushes.len())
+    } else {
+        format!(
+            "{} of {} pushes relayed.",
+            pushes.iter().filter(|push| push.relayed).count(),
+            pushes.len()
+        )
+    };
+
+    let embed = EmbedBuilder::new()
+        .title(if dry_run {
+            "Replay (dry run)"
+        } else {
+            "Replay"
+        })
+        .color(PUSH_EMBED_COLOR)
+        .description(description_of(
+            std::iter::once(summary).chain(pushes.iter().map(ToString::to_string)),
+        ))
+        .validate()?
+        .build();
+
+    http.update_response(&command.token)
+        .embeds(Some(&[embed]))?
+        .exec()
+        .recorded()
+        .await?;
+
+    Ok(())
+}
+
+/// Whether the user of a command can set up the bot in the guild.
+fn is_guild_admin(command: &ApplicationCommand) -> bool {
+    matches!(
+        command.member.as_ref().and_then(|member| member.permissions),
+        Some(permissions) if permissions.intersects(Permissions::ADMINISTRATOR | Permissions::MANAGE_GUILD)
+    )
+}
+
+/// Handle `/apidocs settings`, without options the settings are only shown.
+async fn guild_settings(
+    command: &ApplicationCommand,
+    options: &[CommandDataOption],
+    state: State,
+) -> HandlerResult {
+    let http = state.http.interaction(APPLICATION_ID);
+
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => {
+            http.create_response(
+                command.id,
+                &command.token,
+                &ephemeral_response("Settings only exist in servers."),
+            )
+            .exec()
+            .recorded()
+            .await?;
+
+            return Ok(());
+        }
+    };
+
+    if !is_guild_admin(command) {
+        http.create_response(
+            command.id,
+            &command.token,
+            &ephemeral_response("You need the Manage Server permission for this."),
+        )
+        .exec()
+        .recorded()
+        .await?;
+
+        return Ok(());
+    }
+
+    // Only showing the settings changes nothing, so there is nothing to audit
+    if options.is_empty() {
+        let settings = settings_of_guild(&state, Some(guild_id));
+
+        return show_settings(command, "Settings", &settings, state).await;
+    }
+
+    let entry = audit::entry(
+        command.guild_id,
+        command.author_id(),
+        "/apidocs settings",
+        None,
+    );
+
+    let result = async {
+        let existing = state
+            .database
+            .read(|data| data.guilds.get(&guild_id.get()).cloned());
+        let mut settings = match existing {
+            Some(settings) => settings,
+            None => initial_settings(&state, guild_id).await,
+        };
+
+        if let Err(problem) = change_settings(&mut settings, options, &state.config.issue_repos) {
+            http.create_response(command.id, &command.token, &ephemeral_response(&problem))
+                .exec()
+                .recorded()
+                .await?;
+
+            return Ok(());
+        }
+
+        state
+            .database
+            .write(|data| data.guilds.insert(guild_id.get(), settings.clone()))?;
+        tracing::info!(guild = guild_id.get(), "Changed the settings");
+
+        show_settings(command, "Saved the settings", &settings, Arc::clone(&state)).await
+    }
+    .await;
+    audit::record(&state, entry, &result).await;
+
+    result
+}
+
+/// Apply the options of `/apidocs settings`, options which were not given change nothing.
+///
+/// Returns what is wrong with the options, nothing is changed in that case.
+/// Issues can only be moved into one of the `issue_repos` of the config.
+fn change_settings(
+    settings: &mut GuildSettings,
+    options: &[CommandDataOption],
+    issue_repos: &[String],
+) -> Result<(), String> {
+    let mut changed = settings.clone();
+
+    for option in options {
+        match (option.name.as_str(), &option.value) {
+            ("channel", CommandOptionValue::Channel(channel_id)) => {
+                changed.relay_channel = Some(channel_id.get())
+            }
+            ("webhook", CommandOptionValue::String(value)) => {
+                let webhook_id = value
+                    .trim()
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|webhook_id| *webhook_id != 0)
+                    .ok_or("`webhook` needs to be the id of the webhook.")?;
+                changed.webhook_user = Some(webhook_id);
+            }
+            ("repo", CommandOptionValue::String(value)) => {
+                // The bot can write into every repo its credentials reach, not only the guild's
+                changed.issue_repo = issue_repos
+                    .iter()
+                    .find(|repo| repo.eq_ignore_ascii_case(value.trim()))
+                    .cloned()
+                    .ok_or_else(|| match issue_repos {
+                        [] => "No repo can be chosen, `ISSUE_REPOS` is not set.".to_owned(),
+                        _ => format!("`repo` needs to be one of: {}", issue_repos.join(", ")),
+                    })?;
+            }
+            ("routes", CommandOptionValue::String(value)) => {
+                changed.routes = serde_json::from_str::<Vec<Route>>(value)
+                    .map_err(|err| format!("`routes` are not valid: {}", err))?;
+            }
+            ("title_prefix", CommandOptionValue::String(value)) => {
+                changed.template.title_prefix = match value.trim() {
+                    "none" => String::new(),
+                    prefix => prefix.to_owned(),
+                };
+            }
+            ("labels", CommandOptionValue::String(value)) => {
+                changed.template.labels = match value.trim() {
+                    "none" => vec![],
+                    labels => labels
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|label| !label.is_empty())
+                        .map(String::from)
+                        .collect(),
+                };
+            }
+            ("add_manager", CommandOptionValue::User(user_id))
+                if !changed.managers.contains(&user_id.get()) =>
+            {
+                changed.managers.push(user_id.get())
+            }
+            ("remove_manager", CommandOptionValue::User(user_id)) => {
+                changed.managers.retain(|manager| *manager != user_id.get())
+            }
+            _ => {}
+        }
+    }
+
+    *settings = changed;
+
+    Ok(())
+}
+
+/// Show the settings of a guild as JSON, they are attached as a file if they do not fit.
+async fn show_settings(
+    command: &ApplicationCommand,
+    title: &str,
+    settings: &GuildSettings,
+    state: State,
+) -> HandlerResult {
+    let json = serde_json::to_string_pretty(settings)?;
+
+    let code_block = format!("```json\n{}\n```", json);
+    let (description, attachments) = if code_block.chars().count() <= 4096 {
+        (code_block, None)
+    } else {
+        (
+            "The settings are attached.".to_owned(),
+            Some(vec![Attachment::from_bytes(
+                "settings.json".into(),
+                json.into_bytes(),
+                0,
+            )]),
+        )
+    };
+
+    let embed = EmbedBuilder::new()
+        .title(title)
+        .color(PUSH_EMBED_COLOR)
+        .description(description)
+        .validate()?
+        .build();
+
+    state
+        .http
+        .interaction(APPLICATION_ID)
+        .create_response(
+            command.id,
+            &command.token,
+            &InteractionResponse {
+                kind: InteractionResponseType::ChannelMessageWithSource,
+                data: Some(InteractionResponseData {
+                    attachments,
+                    embeds: Some(vec![embed]),
+                    flags: Some(MessageFlags::EPHEMERAL),
+                    ..Default::default()
+                }),
+            },
+        )
+        .exec()
+        .recorded()
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guilds::empty_settings;
+
+    fn option(name: &str, value: CommandOptionValue) -> CommandDataOption {
+        CommandDataOption {
+            focused: false,
+            name: name.into(),
+            value,
+        }
+    }
+
+    fn string(name: &str, value: &str) -> CommandDataOption {
+        option(name, CommandOptionValue::String(value.into()))
+    }
+
+    fn repos() -> Vec<String> {
+        vec!["discordeno/discordeno".into(), "owner/docs".into()]
+    }
+
+    #[test]
+    fn dates_are_midnight_utc() {
+        assert_eq!(parse_since("2022-05-01"), Some(1_651_363_200));
+        assert_eq!(parse_since("2022-13-01"), None);
+        assert_eq!(parse_since("01.05.2022"), None);
+    }
+
+    #[test]
+    fn ages_are_counted_back_from_now() {
+        for (since, seconds) in [
+            ("0h", 0),
+            ("3h", 3 * 3600),
+            ("2d", 2 * 86400),
+            ("1w", 604_800),
+        ] {
+            let expected = Utc::now().timestamp() - seconds;
+            let parsed = parse_since(since).unwrap();
+
+            // The clock can tick between the two calls
+            assert!((expected - 1..=expected).contains(&parsed), "{}", since);
+        }
+    }
+
+    #[test]
+    fn ages_need_a_known_unit_and_amount() {
+        for since in ["", "7", "d", "7m", "-1d", "1.5d", " 7d", "7 d", "7dd", "7é"] {
+            assert_eq!(parse_since(since), None, "{}", since);
+        }
+    }
+
+    #[test]
+    fn huge_ages_do_not_panic() {
+        assert_eq!(parse_since("99999999999999999999w"), None);
+        assert_eq!(parse_since(&format!("{}w", u32::MAX)), None);
+        assert_eq!(parse_since(&format!("{}h", u32::MAX)), None);
+        assert!(parse_since("100000w").is_some());
+    }
+
+    #[test]
+    fn no_options_change_nothing() {
+        let mut settings = empty_settings();
+
+        assert!(change_settings(&mut settings, &[], &repos()).is_ok());
+        assert_eq!(settings.relay_channel, None);
+        assert_eq!(settings.issue_repo, "");
+        assert_eq!(settings.template.title_prefix, "[api-docs]");
+    }
+
+    #[test]
+    fn repos_need_to_be_allowed() {
+        let mut settings = empty_settings();
+
+        assert!(
+            change_settings(&mut settings, &[string("repo", " Owner/Docs ")], &repos()).is_ok()
+        );
+        assert_eq!(settings.issue_repo, "owner/docs");
+
+        let problem = change_settings(&mut settings, &[string("repo", "someone/else")], &repos())
+            .unwrap_err();
+        assert_eq!(
+            problem,
+            "`repo` needs to be one of: discordeno/discordeno, owner/docs"
+        );
+        assert_eq!(settings.issue_repo, "owner/docs");
+
+        let problem =
+            change_settings(&mut settings, &[string("repo", "owner/docs")], &[]).unwrap_err();
+        assert_eq!(problem, "No repo can be chosen, `ISSUE_REPOS` is not set.");
+    }
+
+    #[test]
+    fn nothing_changes_when_one_option_is_wrong() {
+        let mut settings = empty_settings();
+        let options = [
+            option("channel", CommandOptionValue::Channel(Id::new(1))),
+            string("webhook", "not a webhook"),
+        ];
+
+        assert_eq!(
+            change_settings(&mut settings, &options, &repos()).unwrap_err(),
+            "`webhook` needs to be the id of the webhook."
+        );
+        assert_eq!(settings.relay_channel, None);
+        assert_eq!(settings.webhook_user, None);
+    }
+
+    #[test]
+    fn webhooks_are_non_zero_ids() {
+        let mut settings = empty_settings();
+
+        assert!(change_settings(&mut settings, &[string("webhook", "0")], &repos()).is_err());
+        assert!(change_settings(&mut settings, &[string("webhook", " 42 ")], &repos()).is_ok());
+        assert_eq!(settings.webhook_user, Some(42));
+    }
+
+    #[test]
+    fn routes_are_json() {
+        let mut settings = empty_settings();
+
+        let problem =
+            change_settings(&mut settings, &[string("routes", "[{")], &repos()).unwrap_err();
+        assert!(problem.starts_with("`routes` are not valid: "));
+
+        assert!(change_settings(&mut settings, &[string("routes", "[]")], &repos()).is_ok());
+        assert!(settings.routes.is_empty());
+    }
+
+    #[test]
+    fn none_clears_the_template() {
+        let mut settings = empty_settings();
+        let options = [string("title_prefix", "none"), string("labels", "none")];
+
+        assert!(change_settings(&mut settings, &options, &repos()).is_ok());
+        assert_eq!(settings.template.title_prefix, "");
+        assert!(settings.template.labels.is_empty());
+
+        let options = [
+            string("title_prefix", " [docs] "),
+            string("labels", "docs, ,api ,"),
+        ];
+        assert!(change_settings(&mut settings, &options, &repos()).is_ok());
+        assert_eq!(settings.template.title_prefix, "[docs]");
+        assert_eq!(settings.template.labels, ["docs", "api"]);
+    }
+
+    #[test]
+    fn managers_are_added_once() {
+        let mut settings = empty_settings();
+        let add = option("add_manager", CommandOptionValue::User(Id::new(7)));
+
+        assert!(change_settings(&mut settings, &[add.clone(), add], &repos()).is_ok());
+        assert_eq!(settings.managers, [7]);
+
+        let remove = option("remove_manager", CommandOptionValue::User(Id::new(7)));
+        assert!(change_settings(&mut settings, &[remove], &repos()).is_ok());
+        assert!(settings.managers.is_empty());
+    }
+}
@@ -0,0 +1,252 @@
+use super::{
+    ephemeral_response, is_issue_manager, issues, mirror, schedule, HandlerResult, NO_ISSUE_REPO,
+};
+use crate::{
+    audit,
+    constants::{APPLICATION_ID, ISSUE_TITLE_MAX_LEN},
+    database::{GuildSettings, HistoryEvent},
+    github::{build_issues, fetch_commit_details, fetch_commits, fetch_commits_with},
+    guilds::settings_of_guild,
+    metrics::Recorded,
+    types::State,
+};
+use std::sync::Arc;
+use twilight_model::{
+    application::{
+        component::{text_input::TextInputStyle, TextInput},
+        interaction::MessageComponentInteraction,
+    },
+    http::interaction::{InteractionResponse, InteractionResponseData, InteractionResponseType},
+};
+use util::builder::{ModalBuilder, TextInputBuilder, MAX_TEXT_INPUT_VALUE_LEN};
+
+/// The body input of the issue modal.
+///
+/// Bodies which are too long to pre-fill are left out, the generated body is kept
+/// unless something is written into the input.
+fn body_input(body: &str) -> TextInput {
+    let input = TextInputBuilder::new(TextInputStyle::Paragraph, "body".into(), "Body".into());
+
+    if body.chars().count() <= MAX_TEXT_INPUT_VALUE_LEN.into() {
+        input.required(true).value(body.to_owned()).build()
+    } else {
+        input
+            .required(false)
+            .placeholder(Some(
+                "The body is too long to edit here, leave this empty to keep it".into(),
+            ))
+            .build()
+    }
+}
+
+pub(super) async fn handle_component(
+    component: Box<MessageComponentInteraction>,
+    state: State,
+) -> HandlerResult {
+    let http = state.http.interaction(APPLICATION_ID);
+    let settings = settings_of_guild(&state, component.guild_id);
+
+    if !is_issue_manager(&settings, component.author_id()) {
+        http.create_response(
+            component.id,
+            &component.token,
+            &ephemeral_response("You do not have access to this."),
+        )
+        .exec()
+        .recorded()
+        .await?;
+
+        return Ok(());
+    }
+
+    // The ids of the message are not part of the action
+    let action = match component.data.custom_id.split_once(':') {
+        Some((action, _)) => action,
+        None => component.data.custom_id.as_str(),
+    };
+    let entry = audit::entry(
+        component.guild_id,
+        component.author_id(),
+        action,
+        Some(&component.message),
+    );
+
+    let result = run_component(component, Arc::clone(&state), &settings).await;
+    audit::record(&state, entry, &result).await;
+
+    result
+}
+
+async fn run_component(
+    component: Box<MessageComponentInteraction>,
+    state: State,
+    settings: &GuildSettings,
+) -> HandlerResult {
+    let http = state.http.interaction(APPLICATION_ID);
+
+    let custom_id = component.data.custom_id.as_str();
+    if matches!(custom_id, "create-github-issue" | "create-skipped-issues")
+        && settings.issue_repo.is_empty()
+    {
+        http.create_response(
+            component.id,
+            &component.token,
+            &ephemeral_response(NO_ISSUE_REPO),
+        )
+        .exec()
+        .recorded()
+        .await?;
+
+        return Ok(());
+    }
+
+    match custom_id {
+        "create-github-issue" => {
+            // A message whose embed is gone gets the error below instead of a panic
+            if let Some(url) = component
+                .message
+                .embeds
+                .first()
+                .and_then(|embed| embed.url.as_ref())
+            {
+                // Opening a modal can not be deferred, so the commits need to be
+                // fetched before the interaction token runs out. Only what the skip rules
+                // need is fetched, the modal of a compare only shows the titles.
+                let rules = &state.config.skip_rules;
+                let (mut commits, _) = rules.partition(
+                    fetch_commits_with(&state.github, url, |commit| rules.needs_details(commit))
+                        .await?,
+                );
+                // The body of a single issue is edited in the modal, it needs the changes
+                if let [commit] = commits.as_mut_slice() {
+                    if commit.files.is_empty() {
+                        *commit = fetch_commit_details(&state.github, commit).await?;
+                    }
+                }
+                let issues = build_issues(commits, &settings.template);
+
+                let modal = if issues.len() == 1 {
+                    ModalBuilder::new(
+                        "create-github-issue-modal".into(),
+                        "Create GitHub issue".into(),
+                    )
+                    .text_input(
+                        TextInputBuilder::new(
+                            TextInputStyle::Short,
+                            "title".into(),
+                            "Title".into(),
+                        )
+                        .max_length(Some(ISSUE_TITLE_MAX_LEN))
+                        .required(true)
+                        .value(issues[0].title.clone())
+                        .build(),
+                    )
+                    .text_input(body_input(&issues[0].body))
+                    .build()
+                } else if (2..=5).contains(&issues.len()) {
+                    // A modal only fits 5 inputs so only the titles can be edited for compares
+                    issues
+                        .iter()
+                        .enumerate()
+                        .fold(
+                            ModalBuilder::new(
+                                "create-github-issue-modal".into(),
+                                "Create GitHub issues".into(),
+                            ),
+                            |modal, (index, issue)| {
+                                modal.text_input(
+                                    TextInputBuilder::new(
+                                        TextInputStyle::Short,
+                                        format!("title-{}", index),
+                                        format!("Title of issue {}", index + 1),
+                                    )
+                                    .max_length(Some(ISSUE_TITLE_MAX_LEN))
+                                    .required(true)
+                                    .value(issue.title.clone())
+                                    .build(),
+                                )
+                            },
+                        )
+                        .build()
+                } else {
+                    // Too many issues to edit or all were skipped, so just create them right away
+                    http.create_response(
+                        component.id,
+                        &component.token,
+                        &InteractionResponse {
+                            kind: InteractionResponseType::DeferredUpdateMessage,
+                            data: None,
+                        },
+                    )
+                    .exec()
+                    .recorded()
+                    .await?;
+
+                    // The bodies need the changes of every commit, there is time for that now
+                    let (commits, skipped) =
+                        rules.partition(fetch_commits(&state.github, url).await?);
+
+                    issues::file_issues(
+                        &state,
+                        &component.token,
+                        &settings.issue_repo,
+                        &component.message,
+                        component.author_id(),
+                        build_issues(commits, &settings.template),
+                        &skipped,
+                    )
+                    .await?;
+
+                    return Ok(());
+                };
+
+                http.create_response(component.id, &component.token, &modal)
+                    .exec()
+                    .recorded()
+                    .await?;
+
+                return Ok(());
+            }
+
+            http.create_response(
+                component.id,
+                &component.token,
+                &ephemeral_response("Could not get the issue link."),
+            )
+            .exec()
+            .recorded()
+            .await?;
+        }
+        "delete-message" => {
+            schedule::unschedule(&state, component.message.id)?;
+            state.database.write(|data| {
+                data.log(HistoryEvent::Dismissed {
+                    message_id: component.message.id.get(),
+                })
+            })?;
+
+            http.create_response(
+                component.id,
+                &component.token,
+                &InteractionResponse {
+                    kind: InteractionResponseType::UpdateMessage,
+                    data: Some(InteractionResponseData {
+                        content: Some(String::new()),
+                        components: Some(vec![]),
+                        ..Default::default()
+                    }),
+                },
+            )
+            .exec()
+            .recorded()
+            .await?;
+        }
+        "undo-github-issues" => issues::undo_issues(component, state).await?,
+        "create-skipped-issues" => issues::create_skipped_issues(component, state).await?,
+        "cancel-auto-create" => schedule::cancel_scheduled(component, state).await?,
+        id if id.starts_with("send-to-issue:") => mirror::send_selected(component, state).await?,
+        _ => {}
+    }
+
+    Ok(())
+}
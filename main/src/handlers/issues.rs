@@ -0,0 +1,743 @@
+use super::{dry_run_followup, ephemeral_response, relay::relay_components, HandlerResult};
+use crate::{
+    constants::{APPLICATION_ID, REMOVE_BUTTON_EMOJI},
+    database::{
+        CreatedIssue, CreatedIssues, DatabaseError, GuildSettings, HistoryEvent, IssueJob,
+        IssueStatus,
+    },
+    dry_run::{self, DryRunAction},
+    github::{
+        build_issues, close_issue, fetch_commits, submit_issue, GithubCreateIssue,
+        ISSUE_CREATION_PAUSE,
+    },
+    guilds::settings_of_guild,
+    metrics::Recorded,
+    shutdown::JobGuard,
+    skip::SkippedCommit,
+    threads::mirror_issues,
+    types::{
+        github::{GitHubCommit, GitHubIssue},
+        State,
+    },
+};
+use chrono::Utc;
+use std::{error::Error, sync::Arc};
+use tracing::Span;
+use twilight_model::{
+    application::{
+        component::{button::ButtonStyle, Component},
+        interaction::MessageComponentInteraction,
+    },
+    channel::{message::MessageFlags, Message},
+    http::interaction::{InteractionResponse, InteractionResponseType},
+    id::{
+        marker::{ChannelMarker, MessageMarker, UserMarker},
+        Id,
+    },
+};
+use util::builder::{ButtonBuilder, ComponentBuilder};
+
+/// The buttons of a relayed message once its issues were created.
+fn created_components(created: bool, skipped: bool) -> Vec<Component> {
+    let mut components = ComponentBuilder::new();
+
+    if created {
+        components = components.button(
+            ButtonBuilder::new(ButtonStyle::Danger, "undo-github-issues".into())
+                .label("Undo".into())
+                .build(),
+        );
+    } else {
+        components = components.button(
+            ButtonBuilder::new(ButtonStyle::Secondary, "delete-message".into())
+                .emoji(REMOVE_BUTTON_EMOJI)
+                .build(),
+        );
+    }
+
+    // The skip rules can be overruled for a single message
+    if skipped {
+        components = components.button(
+            ButtonBuilder::new(ButtonStyle::Secondary, "create-skipped-issues".into())
+                .label("Create skipped".into())
+                .build(),
+        );
+    }
+
+    components.build()
+}
+
+/// List what was created and what was skipped, this becomes the content of the relayed message.
+fn summary(created: &[GitHubIssue], skipped: &[String]) -> String {
+    let mut lines = vec![];
+
+    if !created.is_empty() {
+        let links = created
+            .iter()
+            .map(|issue| format!("[#{}](<{}>)", issue.number, issue.html_url))
+            .collect::<Vec<_>>()
+            .join(", ");
+        lines.push(format!("Created {}.", links));
+    }
+
+    if !skipped.is_empty() {
+        lines.push(format!("Skipped {} commit(s):", skipped.len()));
+        lines.extend(
+            skipped
+                .iter()
+                .take(10)
+                .map(|commit| format!("- {}", commit)),
+        );
+        if skipped.len() > 10 {
+            lines.push(format!("- and {} more", skipped.len() - 10));
+        }
+    }
+
+    // Stay within the message content limit
+    lines.join("\n").chars().take(2000).collect()
+}
+
+/// Create the issues for a relayed message and remember them so they can be undone.
+///
+/// The interaction must already be deferred.
+pub(super) async fn file_issues(
+    state: &State,
+    token: &str,
+    repo: &str,
+    message: &Message,
+    user_id: Option<Id<UserMarker>>,
+    issues: Vec<GithubCreateIssue>,
+    skipped: &[SkippedCommit],
+) -> HandlerResult {
+    if state.config.dry_run {
+        let action = DryRunAction::CreateIssues {
+            repo: repo.into(),
+            message_id: message.id.get(),
+            issues,
+        };
+
+        return dry_run_followup(state, token, vec![action]).await;
+    }
+
+    let http = state.http.interaction(APPLICATION_ID);
+
+    if !start_job(state, repo, message, user_id, issues, skipped)? {
+        http.create_followup(token)
+            .content("The issues of this message are already being created.")?
+            .flags(MessageFlags::EPHEMERAL)
+            .exec()
+            .recorded()
+            .await?;
+
+        return Ok(());
+    }
+
+    let job = match run_job(state, message.id).await? {
+        Some(job) => job,
+        None => {
+            // The message is updated once the job is resumed
+            http.update_response(token)
+                .content(Some(INTERRUPTED_CONTENT))?
+                .components(Some(&[]))?
+                .exec()
+                .recorded()
+                .await?;
+
+            return Ok(());
+        }
+    };
+
+    let (content, components) = finish_job(state, message, &job).await?;
+
+    http.update_response(token)
+        .content(Some(&content))?
+        .components(Some(&components))?
+        .exec()
+        .recorded()
+        .await?;
+
+    Ok(())
+}
+
+/// Shown on relayed messages whose issues are still being created while the bot restarts.
+const INTERRUPTED_CONTENT: &str =
+    "The bot is restarting, the remaining issues will be created once it is back.";
+
+/// Persist the issues of a relayed message before any of them is created.
+///
+/// Returns `false` without touching anything if the message already has a job, running both
+/// would create its issues twice.
+fn start_job(
+    state: &State,
+    repo: &str,
+    message: &Message,
+    user_id: Option<Id<UserMarker>>,
+    issues: Vec<GithubCreateIssue>,
+    skipped: &[SkippedCommit],
+) -> Result<bool, DatabaseError> {
+    state.database.write(|data| {
+        if data.jobs.contains_key(&message.id.get()) {
+            return false;
+        }
+
+        data.jobs.insert(
+            message.id.get(),
+            IssueJob {
+                channel_id: message.channel_id.get(),
+                created_by: user_id.map_or(0, Id::get),
+                repo: repo.into(),
+                upstream: message
+                    .embeds
+                    .first()
+                    .and_then(|embed| embed.url.clone())
+                    .unwrap_or_default(),
+                pending: issues,
+                created: vec![],
+                skipped: skipped.iter().map(ToString::to_string).collect(),
+            },
+        );
+
+        true
+    })
+}
+
+/// Create the pending issues of a job one by one, checkpointing after every issue.
+///
+/// Returns the finished job, or `None` if the bot started shutting down first.
+/// The job is kept in that case and resumed on the next start.
+#[tracing::instrument(
+    name = "issue_job",
+    skip(state),
+    fields(
+        message = message_id.get(),
+        user = tracing::field::Empty,
+        upstream = tracing::field::Empty,
+        repo = tracing::field::Empty,
+    ),
+)]
+async fn run_job(
+    state: &State,
+    message_id: Id<MessageMarker>,
+) -> Result<Option<IssueJob>, Box<dyn Error + Send + Sync>> {
+    let _running = JobGuard::new(state);
+
+    let repo = match state
+        .database
+        .read(|data| data.jobs.get(&message_id.get()).cloned())
+    {
+        Some(job) => {
+            let span = Span::current();
+            span.record("user", job.created_by);
+            span.record("upstream", job.upstream.as_str());
+            span.record("repo", job.repo.as_str());
+
+            job.repo
+        }
+        None => return Ok(None),
+    };
+
+    loop {
+        let next = state.database.read(|data| {
+            data.jobs
+                .get(&message_id.get())
+                .and_then(|job| job.pending.first().cloned())
+        });
+        let issue = match next {
+            Some(issue) => issue,
+            None => break,
+        };
+
+        if state.is_shutting_down() {
+            tracing::info!("Stopping for the shutdown, the job is resumed on the next start");
+            return Ok(None);
+        }
+
+        let created = match submit_issue(&state.github, &repo, &issue).await {
+            Ok(created) => created,
+            Err(err) => {
+                tracing::warn!(title = %issue.title, "Could not create an issue: {}", err);
+
+                // Retrying on the next start could file the issues way too late, so give up
+                // and keep what was created so it can still be undone
+                if let Some(job) = state
+                    .database
+                    .write(|data| data.jobs.remove(&message_id.get()))?
+                {
+                    if !job.created.is_empty() {
+                        let message = fetch_job_message(state, message_id, &job).await?;
+                        let (content, components) = finish_job(state, &message, &job).await?;
+
+                        state
+                            .http
+                            .update_message(message.channel_id, message.id)
+                            .content(Some(&content))?
+                            .components(Some(&components))?
+                            .exec()
+                            .recorded()
+                            .await?;
+                    }
+                }
+
+                return Err(err.into());
+            }
+        };
+
+        tracing::info!(number = created.number, title = %created.title, "Created an issue");
+
+        let pending = state
+            .database
+            .write(|data| match data.jobs.get_mut(&message_id.get()) {
+                Some(job) => {
+                    job.pending.remove(0);
+                    job.created.push(created);
+                    job.pending.len()
+                }
+                None => 0,
+            })?;
+
+        if pending != 0 {
+            tokio::time::sleep(ISSUE_CREATION_PAUSE).await;
+        }
+    }
+
+    let job = state
+        .database
+        .write(|data| data.jobs.remove(&message_id.get()))?;
+    tracing::info!("Finished the job");
+
+    Ok(job)
+}
+
+/// Remember the issues of a finished job, returns the content and buttons of its relayed message.
+async fn finish_job(
+    state: &State,
+    message: &Message,
+    job: &IssueJob,
+) -> Result<(String, Vec<Component>), Box<dyn Error + Send + Sync>> {
+    if !job.created.is_empty() {
+        record_issues(
+            state,
+            &job.repo,
+            message,
+            Id::new_checked(job.created_by),
+            &job.created,
+        )
+        .await?;
+    }
+
+    Ok((
+        summary(&job.created, &job.skipped),
+        created_components(!job.created.is_empty(), !job.skipped.is_empty()),
+    ))
+}
+
+async fn fetch_job_message(
+    state: &State,
+    message_id: Id<MessageMarker>,
+    job: &IssueJob,
+) -> Result<Message, Box<dyn Error + Send + Sync>> {
+    Ok(state
+        .http
+        .message(Id::new(job.channel_id), message_id)
+        .exec()
+        .recorded()
+        .await?
+        .model()
+        .await?)
+}
+
+/// Finish a job on its own and update its relayed message, like after a restart.
+async fn complete_job(state: &State, message: &Message) -> HandlerResult {
+    let job = match run_job(state, message.id).await? {
+        Some(job) => job,
+        None => return Ok(()),
+    };
+
+    let (content, components) = finish_job(state, message, &job).await?;
+
+    state
+        .http
+        .update_message(message.channel_id, message.id)
+        .content(Some(&content))?
+        .components(Some(&components))?
+        .exec()
+        .recorded()
+        .await?;
+
+    Ok(())
+}
+
+/// Pick up the issue creation jobs a shutdown interrupted.
+pub fn resume_jobs(state: &State) {
+    let jobs: Vec<(u64, IssueJob)> = state.database.read(|data| {
+        data.jobs
+            .iter()
+            .map(|(message_id, job)| (*message_id, job.clone()))
+            .collect()
+    });
+
+    for (message_id, job) in jobs {
+        let state = Arc::clone(state);
+
+        tokio::spawn(async move {
+            let message_id = Id::new(message_id);
+            let result = async {
+                let message = fetch_job_message(&state, message_id, &job).await?;
+
+                complete_job(&state, &message).await
+            };
+
+            if let Err(err) = result.await {
+                tracing::warn!(
+                    "Could not resume creating the issues of {}: {}",
+                    message_id,
+                    err
+                );
+            }
+        });
+    }
+}
+
+/// Remember the issues created from a relayed message, give the undo button a deadline
+/// and mirror the links into the thread of the message.
+///
+/// Issues created later from the same message are added to the existing ones.
+/// The undo button itself has to be added by the caller.
+pub(super) async fn record_issues(
+    state: &State,
+    repo: &str,
+    message: &Message,
+    user_id: Option<Id<UserMarker>>,
+    created: &[GitHubIssue],
+) -> HandlerResult {
+    state.database.write(|data| {
+        let record = data
+            .created_issues
+            .entry(message.id.get())
+            .or_insert_with(|| CreatedIssues {
+                created_at: 0,
+                created_by: user_id.map_or(0, Id::get),
+                repo: repo.into(),
+                issues: vec![],
+            });
+        record.created_at = Utc::now().timestamp();
+        record.issues.extend(created.iter().map(|issue| {
+            CreatedIssue {
+                number: issue.number,
+                html_url: issue.html_url.clone(),
+                labels: issue
+                    .labels
+                    .iter()
+                    .map(|label| label.name.clone())
+                    .collect(),
+                ..Default::default()
+            }
+        }));
+        data.log(HistoryEvent::IssuesCreated {
+            message_id: message.id.get(),
+            numbers: created.iter().map(|issue| issue.number).collect(),
+        });
+        // Creating the issues by hand replaces the automatic creation
+        data.scheduled.remove(&message.id.get());
+    })?;
+
+    tokio::spawn(expire_undo(
+        Arc::clone(state),
+        message.channel_id,
+        message.id,
+    ));
+
+    // The issues exist either way, so a missing thread is no reason to fail
+    if let Err(err) = mirror_issues(state, message.id.get(), created).await {
+        tracing::warn!(
+            "Could not mirror the issues into the thread of {}: {}",
+            message.id,
+            err
+        );
+    }
+
+    Ok(())
+}
+
+/// Create the issues for a relayed message without anyone clicking the button.
+///
+/// The commits of the message are fetched unless they are given.
+pub(super) async fn auto_create_issues(
+    state: &State,
+    settings: &GuildSettings,
+    message: &Message,
+    commits: Option<Vec<GitHubCommit>>,
+) -> HandlerResult {
+    if settings.issue_repo.is_empty() {
+        tracing::info!(
+            "Not creating the issues of {}, its guild has no issue repo",
+            message.id
+        );
+
+        return Ok(());
+    }
+
+    let url = match message.embeds.first().and_then(|embed| embed.url.clone()) {
+        Some(url) => url,
+        None => return Ok(()),
+    };
+
+    let commits = match commits {
+        Some(commits) => commits,
+        None => fetch_commits(&state.github, &url).await?,
+    };
+    let (commits, skipped) = state.config.skip_rules.partition(commits);
+
+    if state.config.dry_run {
+        let action = DryRunAction::CreateIssues {
+            repo: settings.issue_repo.clone(),
+            message_id: message.id.get(),
+            issues: build_issues(commits, &settings.template),
+        };
+        dry_run::report(state, action).await;
+
+        return Ok(());
+    }
+
+    // Someone could have clicked the button while the commits were fetched
+    if !start_job(
+        state,
+        &settings.issue_repo,
+        message,
+        None,
+        build_issues(commits, &settings.template),
+        &skipped,
+    )? {
+        return Ok(());
+    }
+
+    complete_job(state, message).await
+}
+
+/// Create the issues for the commits the skip rules left out.
+pub(super) async fn create_skipped_issues(
+    component: Box<MessageComponentInteraction>,
+    state: State,
+) -> HandlerResult {
+    let http = state.http.interaction(APPLICATION_ID);
+
+    let url = match component
+        .message
+        .embeds
+        .first()
+        .and_then(|embed| embed.url.clone())
+    {
+        Some(url) => url,
+        None => {
+            http.create_response(
+                component.id,
+                &component.token,
+                &ephemeral_response("Could not get the issue link."),
+            )
+            .exec()
+            .recorded()
+            .await?;
+
+            return Ok(());
+        }
+    };
+
+    http.create_response(
+        component.id,
+        &component.token,
+        &InteractionResponse {
+            kind: InteractionResponseType::DeferredUpdateMessage,
+            data: None,
+        },
+    )
+    .exec()
+    .recorded()
+    .await?;
+
+    let (_, skipped) = state
+        .config
+        .skip_rules
+        .partition(fetch_commits(&state.github, &url).await?);
+    let settings = settings_of_guild(&state, component.guild_id);
+    let issues = build_issues(
+        skipped.into_iter().map(|skipped| skipped.commit).collect(),
+        &settings.template,
+    );
+
+    file_issues(
+        &state,
+        &component.token,
+        &settings.issue_repo,
+        &component.message,
+        component.author_id(),
+        issues,
+        &[],
+    )
+    .await
+}
+
+/// Take the undo button away again once the undo window is over, the other buttons stay.
+async fn expire_undo(state: State, channel_id: Id<ChannelMarker>, message_id: Id<MessageMarker>) {
+    tokio::time::sleep(state.config.undo_window).await;
+
+    // The issues could have been undone in the meantime, which restores the relay buttons
+    let undone = state
+        .database
+        .read(|data| match data.created_issues.get(&message_id.get()) {
+            Some(created) => created.issues.iter().all(|issue| issue.closed),
+            None => true,
+        });
+    if undone {
+        return;
+    }
+
+    let result = async {
+        let message = state
+            .http
+            .message(channel_id, message_id)
+            .exec()
+            .recorded()
+            .await?
+            .model()
+            .await?;
+
+        state
+            .http
+            .update_message(channel_id, message_id)
+            .components(Some(&without_undo(message.components)))?
+            .exec()
+            .recorded()
+            .await?;
+
+        Ok::<_, Box<dyn Error + Send + Sync>>(())
+    };
+
+    if let Err(err) = result.await {
+        tracing::warn!(
+            "Could not remove the undo button from {}: {}",
+            message_id,
+            err
+        );
+    }
+}
+
+/// Drop the undo button and the rows it leaves empty.
+fn without_undo(components: Vec<Component>) -> Vec<Component> {
+    components
+        .into_iter()
+        .filter_map(|component| match component {
+            Component::ActionRow(mut row) => {
+                row.components.retain(|component| {
+                    !matches!(
+                        component,
+                        Component::Button(button)
+                            if button.custom_id.as_deref() == Some("undo-github-issues")
+                    )
+                });
+
+                (!row.components.is_empty()).then_some(Component::ActionRow(row))
+            }
+            component => Some(component),
+        })
+        .collect()
+}
+
+/// Close every issue which was created from the message of the interaction.
+pub(super) async fn undo_issues(
+    component: Box<MessageComponentInteraction>,
+    state: State,
+) -> HandlerResult {
+    let http = state.http.interaction(APPLICATION_ID);
+
+    let created = state.database.read(|data| {
+        data.created_issues
+            .get(&component.message.id.get())
+            .cloned()
+    });
+
+    let created = match created {
+        Some(created) => created,
+        None => {
+            http.create_response(
+                component.id,
+                &component.token,
+                &ephemeral_response("There are no issues recorded for this message."),
+            )
+            .exec()
+            .recorded()
+            .await?;
+
+            return Ok(());
+        }
+    };
+
+    if Utc::now().timestamp() - created.created_at > state.config.undo_window.as_secs() as i64 {
+        http.create_response(
+            component.id,
+            &component.token,
+            &ephemeral_response("It is too late to undo this, use `/apidocs close` instead."),
+        )
+        .exec()
+        .recorded()
+        .await?;
+
+        return Ok(());
+    }
+
+    http.create_response(
+        component.id,
+        &component.token,
+        &InteractionResponse {
+            kind: InteractionResponseType::DeferredUpdateMessage,
+            data: None,
+        },
+    )
+    .exec()
+    .recorded()
+    .await?;
+
+    if state.config.dry_run {
+        let actions = created
+            .issues
+            .iter()
+            .filter(|issue| !issue.closed)
+            .map(|issue| DryRunAction::CloseIssue {
+                repo: created.repo.clone(),
+                number: issue.number,
+                invalid: true,
+            })
+            .collect();
+
+        return dry_run_followup(&state, &component.token, actions).await;
+    }
+
+    for issue in created.issues.iter().filter(|issue| !issue.closed) {
+        close_issue(&state.github, &created.repo, issue.number, true).await?;
+
+        state.database.write(|data| {
+            if let Some(created) = data.created_issues.get_mut(&component.message.id.get()) {
+                for recorded in created
+                    .issues
+                    .iter_mut()
+                    .filter(|recorded| recorded.number == issue.number)
+                {
+                    recorded.closed = true;
+                    recorded.status = IssueStatus::Closed;
+                }
+            }
+            data.log(HistoryEvent::IssueClosed {
+                repo: created.repo.clone(),
+                number: issue.number,
+            });
+        })?;
+    }
+
+    // Bring the relay buttons back so the issues can be created again properly
+    http.update_response(&component.token)
+        .components(Some(&relay_components()))?
+        .exec()
+        .recorded()
+        .await?;
+
+    Ok(())
+}
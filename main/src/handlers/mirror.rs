@@ -0,0 +1,318 @@
+use super::{dry_run_followup, ephemeral_response, is_issue_manager, HandlerResult};
+use crate::{
+    constants::APPLICATION_ID,
+    database::{CreatedIssue, Data},
+    dry_run::{self, DryRunAction},
+    github::comment_on_issue,
+    guilds::{settings_of_channel, settings_of_guild},
+    metrics::Recorded,
+    types::State,
+};
+use twilight_model::{
+    application::interaction::{ApplicationCommand, MessageComponentInteraction},
+    channel::{message::MessageFlags, Message},
+    http::interaction::{InteractionResponse, InteractionResponseData, InteractionResponseType},
+    id::{
+        marker::{ChannelMarker, GuildMarker, MessageMarker},
+        Id,
+    },
+};
+use util::builder::{ComponentBuilder, SelectMenuBuilder, SelectMenuOptionBuilder};
+
+/// The repo and the open issues a message belongs to, either as the relayed message or as part
+/// of its thread.
+fn linked_issues(
+    data: &Data,
+    channel_id: Id<ChannelMarker>,
+    message_id: Id<MessageMarker>,
+) -> (String, Vec<CreatedIssue>) {
+    let relayed_id = if data.created_issues.contains_key(&message_id.get()) {
+        Some(message_id.get())
+    } else {
+        data.threads
+            .iter()
+            .find(|(_, thread_id)| **thread_id == channel_id.get())
+            .map(|(relayed_id, _)| *relayed_id)
+    };
+
+    relayed_id
+        .and_then(|relayed_id| data.created_issues.get(&relayed_id))
+        .map(|created| {
+            (
+                created.repo.clone(),
+                created
+                    .issues
+                    .iter()
+                    .filter(|issue| !issue.closed)
+                    .cloned()
+                    .collect(),
+            )
+        })
+        .unwrap_or_default()
+}
+
+/// Quote a Discord message for a GitHub comment, crediting its author.
+fn comment_body(message: &Message, guild_id: Option<Id<GuildMarker>>) -> String {
+    let mut lines: Vec<String> = message
+        .content
+        .lines()
+        .map(|line| format!("> {}", line))
+        .collect();
+
+    if !message.attachments.is_empty() {
+        lines.push(String::new());
+        lines.push("Attachments:".into());
+        lines.extend(
+            message
+                .attachments
+                .iter()
+                .map(|attachment| format!("- [{}]({})", attachment.filename, attachment.url)),
+        );
+    }
+
+    let guild = guild_id
+        .or(message.guild_id)
+        .map_or_else(|| "@me".to_owned(), |guild_id| guild_id.to_string());
+    lines.push(String::new());
+    lines.push(format!(
+        "— {} [on Discord](https://discord.com/channels/{}/{}/{})",
+        message.author.name, guild, message.channel_id, message.id
+    ));
+
+    lines.join("\n")
+}
+
+/// Handle the `Send to issue` message command.
+pub(super) async fn send_to_issue(command: &ApplicationCommand, state: State) -> HandlerResult {
+    let http = state.http.interaction(APPLICATION_ID);
+
+    let message = match command.data.target_id.and_then(|target_id| {
+        command
+            .data
+            .resolved
+            .as_ref()?
+            .messages
+            .get(&target_id.cast())
+    }) {
+        Some(message) => message,
+        None => return Ok(()),
+    };
+
+    let (repo, issues) = state
+        .database
+        .read(|data| linked_issues(data, message.channel_id, message.id));
+
+    let issue = match issues.as_slice() {
+        [] => {
+            http.create_response(
+                command.id,
+                &command.token,
+                &ephemeral_response("There is no open issue linked to this message."),
+            )
+            .exec()
+            .recorded()
+            .await?;
+
+            return Ok(());
+        }
+        [issue] => issue,
+        _ => {
+            // Compares have an issue per commit, so ask which ones it is about
+            let menu = SelectMenuBuilder::new(format!(
+                "send-to-issue:{}:{}",
+                message.channel_id, message.id
+            ))
+            .placeholder(Some("Choose the issues".into()))
+            .max_values(Some(issues.len().min(25) as u8))
+            .add_options(
+                issues
+                    .iter()
+                    .take(25)
+                    .map(|issue| {
+                        SelectMenuOptionBuilder::new(
+                            format!("#{}", issue.number),
+                            issue.number.to_string(),
+                        )
+                        .build()
+                    })
+                    .collect(),
+            )
+            .build();
+
+            http.create_response(
+                command.id,
+                &command.token,
+                &InteractionResponse {
+                    kind: InteractionResponseType::ChannelMessageWithSource,
+                    data: Some(InteractionResponseData {
+                        components: Some(ComponentBuilder::new().select_menu(menu).build()),
+                        flags: Some(MessageFlags::EPHEMERAL),
+                        ..Default::default()
+                    }),
+                },
+            )
+            .exec()
+            .recorded()
+            .await?;
+
+            return Ok(());
+        }
+    };
+
+    http.create_response(
+        command.id,
+        &command.token,
+        &InteractionResponse {
+            kind: InteractionResponseType::DeferredChannelMessageWithSource,
+            data: Some(InteractionResponseData {
+                flags: Some(MessageFlags::EPHEMERAL),
+                ..Default::default()
+            }),
+        },
+    )
+    .exec()
+    .recorded()
+    .await?;
+
+    let body = comment_body(message, command.guild_id);
+    if state.config.dry_run {
+        let action = DryRunAction::Comment {
+            repo,
+            number: issue.number,
+            body,
+        };
+
+        return dry_run_followup(&state, &command.token, vec![action]).await;
+    }
+
+    comment_on_issue(&state.github, &repo, issue.number, &body).await?;
+
+    http.update_response(&command.token)
+        .content(Some(&format!("Sent to issue #{}.", issue.number)))?
+        .exec()
+        .recorded()
+        .await?;
+
+    Ok(())
+}
+
+/// Send a message to the issues chosen in the select menu of `send_to_issue`.
+pub(super) async fn send_selected(
+    component: Box<MessageComponentInteraction>,
+    state: State,
+) -> HandlerResult {
+    let http = state.http.interaction(APPLICATION_ID);
+
+    let (channel_id, message_id) = match component
+        .data
+        .custom_id
+        .strip_prefix("send-to-issue:")
+        .and_then(|ids| ids.split_once(':'))
+        .and_then(|(channel_id, message_id)| {
+            Some((
+                Id::new_checked(channel_id.parse().ok()?)?,
+                Id::new_checked(message_id.parse().ok()?)?,
+            ))
+        }) {
+        Some(ids) => ids,
+        None => return Ok(()),
+    };
+
+    http.create_response(
+        component.id,
+        &component.token,
+        &InteractionResponse {
+            kind: InteractionResponseType::DeferredUpdateMessage,
+            data: None,
+        },
+    )
+    .exec()
+    .recorded()
+    .await?;
+
+    let message = state
+        .http
+        .message(channel_id, message_id)
+        .exec()
+        .recorded()
+        .await?
+        .model()
+        .await?;
+    let body = comment_body(&message, component.guild_id);
+    let (repo, _) = state
+        .database
+        .read(|data| linked_issues(data, channel_id, message_id));
+    let numbers = component
+        .data
+        .values
+        .iter()
+        .filter_map(|value| value.parse::<i64>().ok());
+
+    if state.config.dry_run {
+        let actions = numbers
+            .map(|number| DryRunAction::Comment {
+                repo: repo.clone(),
+                number,
+                body: body.clone(),
+            })
+            .collect();
+
+        return dry_run_followup(&state, &component.token, actions).await;
+    }
+
+    let mut sent = vec![];
+    for number in numbers {
+        comment_on_issue(&state.github, &repo, number, &body).await?;
+        sent.push(format!("#{}", number));
+    }
+
+    http.update_response(&component.token)
+        .content(Some(&format!("Sent to issue {}.", sent.join(", "))))?
+        .components(Some(&[]))?
+        .exec()
+        .recorded()
+        .await?;
+
+    Ok(())
+}
+
+/// Mirror a message written in the thread of a relayed push into its issue.
+///
+/// Only threads with exactly one open issue are mirrored, for compares it is not clear
+/// which issue a message is about. Like everything else which writes into the issues,
+/// only the messages of issue managers are mirrored.
+pub(super) async fn mirror_thread_message(message: &Message, state: &State) -> HandlerResult {
+    if message.author.bot || settings_of_channel(state, message.channel_id).is_some() {
+        return Ok(());
+    }
+
+    // Like a message which only has an embed or a sticker
+    if message.content.trim().is_empty() && message.attachments.is_empty() {
+        return Ok(());
+    }
+
+    let settings = settings_of_guild(state, message.guild_id);
+    if !is_issue_manager(&settings, Some(message.author.id)) {
+        return Ok(());
+    }
+
+    let (repo, issues) = state
+        .database
+        .read(|data| linked_issues(data, message.channel_id, message.id));
+
+    if let [issue] = issues.as_slice() {
+        let body = comment_body(message, None);
+        if state.config.dry_run {
+            let action = DryRunAction::Comment {
+                repo,
+                number: issue.number,
+                body,
+            };
+            dry_run::report(state, action).await;
+        } else {
+            comment_on_issue(&state.github, &repo, issue.number, &body).await?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,147 @@
+mod command;
+mod component;
+mod issues;
+mod mirror;
+mod modal;
+mod relay;
+mod schedule;
+
+pub use command::commands;
+pub use issues::resume_jobs;
+pub(crate) use relay::{relay_everywhere, relay_webhook_message, route_relay, webhook_push};
+pub use schedule::resume_scheduled;
+
+use crate::{
+    constants::APPLICATION_ID,
+    database::GuildSettings,
+    dry_run::{self, DryRunAction},
+    metrics::Recorded,
+    types::State,
+};
+use std::error::Error;
+use tracing::{Instrument, Span};
+use twilight_gateway::Event;
+use twilight_model::{
+    application::interaction::Interaction,
+    channel::message::MessageFlags,
+    http::interaction::{InteractionResponse, InteractionResponseData, InteractionResponseType},
+    id::{marker::UserMarker, Id},
+};
+
+pub type HandlerResult = Result<(), Box<dyn Error + Send + Sync>>;
+
+pub async fn handle_event(shard_id: u64, event: Event, state: State) -> HandlerResult {
+    match event {
+        Event::MessageCreate(message) => {
+            if state.config.mirror_threads {
+                if let Err(err) = mirror::mirror_thread_message(&message, &state).await {
+                    tracing::warn!("Could not mirror a thread message into its issue: {}", err);
+                }
+            }
+
+            relay::handle_message_create(message, state).await?
+        }
+        Event::ShardConnected(_) => {
+            tracing::info!(shard_id, "Connected");
+        }
+        Event::InteractionCreate(interaction) => {
+            let span = interaction_span(&interaction.0);
+
+            async move {
+                tracing::info!("Handling interaction");
+
+                match interaction.0 {
+                    Interaction::ApplicationCommand(command) => {
+                        command::handle_command(command, state).await
+                    }
+                    Interaction::MessageComponent(component) => {
+                        component::handle_component(component, state).await
+                    }
+                    Interaction::ModalSubmit(modal) => {
+                        modal::handle_modal_submit(modal, state).await
+                    }
+                    _ => Ok(()),
+                }
+            }
+            .instrument(span)
+            .await?
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// A span with who did what on which message, so every button press can be followed in the logs.
+fn interaction_span(interaction: &Interaction) -> Span {
+    let (id, user, message, action) = match interaction {
+        Interaction::ApplicationCommand(command) => (
+            command.id,
+            command.author_id(),
+            command.data.target_id.map(Id::get),
+            command.data.name.as_str(),
+        ),
+        Interaction::MessageComponent(component) => (
+            component.id,
+            component.author_id(),
+            Some(component.message.id.get()),
+            component.data.custom_id.as_str(),
+        ),
+        Interaction::ModalSubmit(modal) => (
+            modal.id,
+            modal.author_id(),
+            modal.message.as_ref().map(|message| message.id.get()),
+            modal.data.custom_id.as_str(),
+        ),
+        _ => return tracing::info_span!("interaction"),
+    };
+
+    tracing::info_span!(
+        "interaction",
+        id = id.get(),
+        user = ?user.map(Id::get),
+        message = ?message,
+        action,
+    )
+}
+
+/// Issues can not be created or closed until the guild chose a repo for them.
+const NO_ISSUE_REPO: &str = "No issue repo is set yet, choose one with `/apidocs settings repo`.";
+
+fn is_issue_manager(settings: &GuildSettings, user_id: Option<Id<UserMarker>>) -> bool {
+    match user_id {
+        Some(id) => settings.managers.contains(&id.get()),
+        None => false,
+    }
+}
+
+/// Report what would have been done in dry-run mode and tell the user of a deferred interaction.
+async fn dry_run_followup(state: &State, token: &str, actions: Vec<DryRunAction>) -> HandlerResult {
+    let summaries: Vec<String> = actions.iter().map(DryRunAction::summary).collect();
+    for action in actions {
+        dry_run::report(state, action).await;
+    }
+
+    state
+        .http
+        .interaction(APPLICATION_ID)
+        .create_followup(token)
+        .content(&summaries.join("\n"))?
+        .flags(MessageFlags::EPHEMERAL)
+        .exec()
+        .recorded()
+        .await?;
+
+    Ok(())
+}
+
+fn ephemeral_response(content: &str) -> InteractionResponse {
+    InteractionResponse {
+        kind: InteractionResponseType::ChannelMessageWithSource,
+        data: Some(InteractionResponseData {
+            content: Some(content.into()),
+            flags: Some(MessageFlags::EPHEMERAL),
+            ..Default::default()
+        }),
+    }
+}
@@ -0,0 +1,146 @@
+use super::{ephemeral_response, is_issue_manager, issues, HandlerResult, NO_ISSUE_REPO};
+use crate::{
+    audit,
+    constants::APPLICATION_ID,
+    database::GuildSettings,
+    github::{build_issues, fetch_commits},
+    guilds::settings_of_guild,
+    metrics::Recorded,
+    types::State,
+};
+use twilight_model::{
+    application::interaction::modal::ModalSubmitInteraction,
+    http::interaction::{InteractionResponse, InteractionResponseType},
+};
+
+pub(super) async fn handle_modal_submit(
+    modal: Box<ModalSubmitInteraction>,
+    state: State,
+) -> HandlerResult {
+    if modal.data.custom_id != "create-github-issue-modal" {
+        return Ok(());
+    }
+
+    let http = state.http.interaction(APPLICATION_ID);
+    let settings = settings_of_guild(&state, modal.guild_id);
+
+    if !is_issue_manager(&settings, modal.author_id()) {
+        http.create_response(
+            modal.id,
+            &modal.token,
+            &ephemeral_response("You do not have access to this."),
+        )
+        .exec()
+        .recorded()
+        .await?;
+
+        return Ok(());
+    }
+
+    if settings.issue_repo.is_empty() {
+        http.create_response(modal.id, &modal.token, &ephemeral_response(NO_ISSUE_REPO))
+            .exec()
+            .recorded()
+            .await?;
+
+        return Ok(());
+    }
+
+    let entry = audit::entry(
+        modal.guild_id,
+        modal.author_id(),
+        &modal.data.custom_id,
+        modal.message.as_ref(),
+    );
+
+    let result = create_from_modal(&modal, &state, &settings).await;
+    audit::record(&state, entry, &result).await;
+
+    result
+}
+
+async fn create_from_modal(
+    modal: &ModalSubmitInteraction,
+    state: &State,
+    settings: &GuildSettings,
+) -> HandlerResult {
+    let http = state.http.interaction(APPLICATION_ID);
+
+    // The modal is opened from the relayed message, so it has to be there.
+    let (message, url) = match modal.message.as_ref().and_then(|message| {
+        message
+            .embeds
+            .first()
+            .and_then(|embed| embed.url.clone())
+            .map(|url| (message, url))
+    }) {
+        Some(found) => found,
+        None => {
+            http.create_response(
+                modal.id,
+                &modal.token,
+                &ephemeral_response("Could not get the issue link."),
+            )
+            .exec()
+            .recorded()
+            .await?;
+
+            return Ok(());
+        }
+    };
+
+    http.create_response(
+        modal.id,
+        &modal.token,
+        &InteractionResponse {
+            kind: InteractionResponseType::DeferredUpdateMessage,
+            data: None,
+        },
+    )
+    .exec()
+    .recorded()
+    .await?;
+
+    // The skip rules give the same result as when the modal was opened, so the inputs still line up
+    let (commits, skipped) = state
+        .config
+        .skip_rules
+        .partition(fetch_commits(&state.github, &url).await?);
+    let mut issues = build_issues(commits, &settings.template);
+
+    for input in modal.data.components.iter().flat_map(|row| &row.components) {
+        match input.custom_id.as_str() {
+            "title" => {
+                if let Some(issue) = issues.first_mut() {
+                    issue.title = input.value.clone();
+                }
+            }
+            // Left empty when the generated body was too long to pre-fill
+            "body" if !input.value.is_empty() => {
+                if let Some(issue) = issues.first_mut() {
+                    issue.body = input.value.clone();
+                }
+            }
+            custom_id => {
+                if let Some(issue) = custom_id
+                    .strip_prefix("title-")
+                    .and_then(|index| index.parse::<usize>().ok())
+                    .and_then(|index| issues.get_mut(index))
+                {
+                    issue.title = input.value.clone();
+                }
+            }
+        }
+    }
+
+    issues::file_issues(
+        state,
+        &modal.token,
+        &settings.issue_repo,
+        message,
+        modal.author_id(),
+        issues,
+        &skipped,
+    )
+    .await
+}
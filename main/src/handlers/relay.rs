@@ -0,0 +1,280 @@
+use super::{issues::auto_create_issues, schedule, HandlerResult};
+use crate::{
+    constants::{ISSUE_BUTTON_EMOJI, REMOVE_BUTTON_EMOJI},
+    database::{GuildSettings, HistoryEvent},
+    dry_run::{self, DryRunAction},
+    embeds::relay_embed,
+    github::{build_issues, fetch_commits},
+    guilds::{relay_targets, settings_of_channel},
+    metrics::{Recorded, METRICS},
+    routes::{route_push, BranchAction},
+    threads::open_push_thread,
+    types::{github::GitHubCommit, State},
+};
+use chrono::Utc;
+use std::{error::Error, sync::atomic::Ordering};
+use twilight_model::{
+    application::component::{button::ButtonStyle, Component},
+    channel::{embed::Embed, Message},
+    gateway::payload::incoming::MessageCreate,
+    id::Id,
+};
+use util::builder::{ButtonBuilder, ComponentBuilder};
+
+/// The buttons every relayed message starts out with.
+pub(super) fn relay_components() -> Vec<Component> {
+    ComponentBuilder::new()
+        .button(
+            ButtonBuilder::new(ButtonStyle::Primary, "create-github-issue".into())
+                .emoji(ISSUE_BUTTON_EMOJI)
+                .build(),
+        )
+        .button(
+            ButtonBuilder::new(ButtonStyle::Secondary, "delete-message".into())
+                .emoji(REMOVE_BUTTON_EMOJI)
+                .build(),
+        )
+        .build()
+}
+
+/// Relay the embeds of a push into the relay channel of a guild, how depends on the route of
+/// the branch in that guild.
+///
+/// The embed is rebuilt from the commits it links, the given embeds are only relayed as they are
+/// if that fails. Returns whether anything was posted, which is never the case in dry-run mode.
+#[tracing::instrument(skip(state, settings, embeds), fields(channel = ?settings.relay_channel))]
+pub(crate) async fn route_relay(
+    state: &State,
+    settings: &GuildSettings,
+    repo: &str,
+    branch: &str,
+    embeds: &[Embed],
+) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let channel_id = match settings.relay_channel {
+        Some(channel_id) => Id::new(channel_id),
+        None => return Ok(false),
+    };
+    let (color, action) = match route_push(&settings.routes, repo, branch) {
+        Some((route, action)) => (route.color, action),
+        None => (None, BranchAction::Ignore),
+    };
+
+    // In auto mode the issues of relayed pushes get created unless someone vetoes it in time
+    let due = match (action, state.config.auto_create_delay) {
+        (BranchAction::Relay, Some(delay)) => Some(Utc::now().timestamp() + delay.as_secs() as i64),
+        _ => None,
+    };
+
+    let components = match action {
+        BranchAction::Relay if due.is_some() => schedule::scheduled_components(),
+        BranchAction::Relay => relay_components(),
+        BranchAction::RelayReadOnly | BranchAction::AutoCreate => vec![],
+        BranchAction::Ignore => return Ok(false),
+    };
+    let content = due.map(schedule::countdown_content).unwrap_or_default();
+
+    // The embed, the thread and the issues all need the commits, so they are fetched once
+    let url = embeds.first().and_then(|embed| embed.url.clone());
+    let commits = match &url {
+        Some(url) => match fetch_commits(&state.github, url).await {
+            Ok(commits) => Some(commits),
+            Err(err) => {
+                tracing::warn!(
+                    "Could not fetch the commits of a push, relaying it as is: {}",
+                    err
+                );
+                None
+            }
+        },
+        None => None,
+    };
+
+    let rebuilt = match (url, &commits) {
+        (Some(url), Some(commits)) => match rebuild_embed(repo, branch, url, commits, color) {
+            Ok(embed) => embed,
+            Err(err) => {
+                tracing::warn!(
+                    "Could not rebuild the embed of a push, relaying it as is: {}",
+                    err
+                );
+                None
+            }
+        },
+        _ => None,
+    };
+    let embeds = match &rebuilt {
+        Some(embed) => std::slice::from_ref(embed),
+        None => embeds,
+    };
+
+    if state.config.dry_run {
+        // Issues which get created without anyone pressing a button are part of the relay
+        let issues = match &commits {
+            Some(commits) if due.is_some() || action == BranchAction::AutoCreate => {
+                let (commits, _) = state.config.skip_rules.partition(commits.clone());
+                build_issues(commits, &settings.template)
+            }
+            _ => vec![],
+        };
+
+        dry_run::report(
+            state,
+            DryRunAction::Relay {
+                channel_id: channel_id.get(),
+                repo: repo.into(),
+                branch: branch.into(),
+                route: action,
+                content,
+                embeds: embeds.to_vec(),
+                issues,
+            },
+        )
+        .await;
+
+        return Ok(false);
+    }
+
+    let message = state
+        .http
+        .create_message(channel_id)
+        .content(&content)?
+        .embeds(embeds)?
+        .components(&components)?
+        .exec()
+        .recorded()
+        .await?
+        .model()
+        .await?;
+
+    METRICS.relays.fetch_add(1, Ordering::Relaxed);
+    tracing::info!(message = message.id.get(), ?action, "Relayed a push");
+
+    state.database.write(|data| {
+        data.relay_channels
+            .insert(message.id.get(), channel_id.get());
+        data.log(HistoryEvent::Relayed {
+            message_id: message.id.get(),
+            title: embeds
+                .first()
+                .and_then(|embed| embed.title.clone())
+                .unwrap_or_default(),
+        })
+    })?;
+
+    if state.config.thread_per_push {
+        let commits = commits.as_deref().unwrap_or_default();
+        if let Err(err) = open_push_thread(state, &message, commits).await {
+            tracing::warn!("Could not open a thread for {}: {}", message.id, err);
+        }
+    }
+
+    match due {
+        Some(due) => schedule::schedule_issues(state, message.id, due)?,
+        None if action == BranchAction::AutoCreate => {
+            auto_create_issues(state, settings, &message, commits).await?
+        }
+        None => {}
+    }
+
+    Ok(true)
+}
+
+/// Build a richer embed for a push from the commits its embed links, `None` if there are none.
+fn rebuild_embed(
+    repo: &str,
+    branch: &str,
+    url: String,
+    commits: &[GitHubCommit],
+    color: Option<u32>,
+) -> Result<Option<Embed>, Box<dyn Error + Send + Sync>> {
+    if commits.is_empty() {
+        return Ok(None);
+    }
+
+    let repo_name = repo.rsplit('/').next().unwrap_or(repo);
+
+    Ok(Some(relay_embed(repo_name, branch, url, commits, color)?))
+}
+
+/// Read the repo and branch out of a title like `[discord-api-docs:master] 1 new commit`.
+fn parse_push_title(title: &str) -> Option<(&str, &str)> {
+    let (target, _) = title.strip_prefix('[')?.split_once(']')?;
+
+    target.split_once(':')
+}
+
+/// The repo and branch of a push the GitHub webhook integration of a guild posted,
+/// `None` for any other message.
+pub(crate) fn webhook_push<'a>(
+    settings: &GuildSettings,
+    message: &'a Message,
+) -> Option<(&'a str, &'a str)> {
+    // Messages could be send from someone else so check the author
+    if settings.webhook_user != Some(message.author.id.get()) {
+        return None;
+    }
+
+    message
+        .embeds
+        .first()
+        .and_then(|embed| embed.title.as_deref())
+        .and_then(parse_push_title)
+}
+
+pub(super) async fn handle_message_create(
+    message: Box<MessageCreate>,
+    state: State,
+) -> HandlerResult {
+    if let Some(settings) = settings_of_channel(&state, message.channel_id) {
+        relay_webhook_message(&state, &settings, &message).await?;
+    }
+
+    Ok(())
+}
+
+/// Relay a push the GitHub webhook integration posted into the relay channel of a guild,
+/// replacing it.
+///
+/// Returns whether the push was relayed.
+pub(crate) async fn relay_webhook_message(
+    state: &State,
+    settings: &GuildSettings,
+    message: &Message,
+) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    // Somehow no embed or not a push, so just return
+    let (repo, branch) = match webhook_push(settings, message) {
+        Some(target) => target,
+        None => return Ok(false),
+    };
+
+    // The route decides if the branch is relayed at all, the original stays if it is not
+    if !route_relay(state, settings, repo, branch, &message.embeds).await? {
+        return Ok(false);
+    }
+
+    state
+        .http
+        .delete_message(message.channel_id, message.id)
+        .exec()
+        .recorded()
+        .await?;
+
+    Ok(true)
+}
+
+/// Relay a push which did not come through the webhook integration into every guild.
+///
+/// A guild failing to get it does not keep the others from getting it.
+pub(crate) async fn relay_everywhere(state: &State, repo: &str, branch: &str, embeds: &[Embed]) {
+    for settings in relay_targets(state) {
+        if let Err(err) = route_relay(state, &settings, repo, branch, embeds).await {
+            tracing::warn!(
+                channel = ?settings.relay_channel,
+                "Could not relay a push to {}:{}: {}",
+                repo,
+                branch,
+                err
+            );
+        }
+    }
+}
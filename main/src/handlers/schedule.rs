@@ -0,0 +1,192 @@
+use super::{
+    ephemeral_response, issues::auto_create_issues, relay::relay_components, HandlerResult,
+};
+use crate::{
+    constants::{APPLICATION_ID, ISSUE_BUTTON_EMOJI, REMOVE_BUTTON_EMOJI},
+    database::DatabaseError,
+    guilds::{settings_of_channel, settings_of_guild},
+    metrics::Recorded,
+    types::State,
+};
+use chrono::Utc;
+use std::{sync::Arc, time::Duration};
+use twilight_model::{
+    application::{
+        component::{button::ButtonStyle, Component},
+        interaction::MessageComponentInteraction,
+    },
+    http::interaction::{InteractionResponse, InteractionResponseData, InteractionResponseType},
+    id::{marker::MessageMarker, Id},
+};
+use util::builder::{ButtonBuilder, ComponentBuilder};
+
+/// The relay buttons plus the button to veto the automatic issue creation.
+pub(super) fn scheduled_components() -> Vec<Component> {
+    ComponentBuilder::new()
+        .button(
+            ButtonBuilder::new(ButtonStyle::Primary, "create-github-issue".into())
+                .emoji(ISSUE_BUTTON_EMOJI)
+                .build(),
+        )
+        .button(
+            ButtonBuilder::new(ButtonStyle::Secondary, "delete-message".into())
+                .emoji(REMOVE_BUTTON_EMOJI)
+                .build(),
+        )
+        .button(
+            ButtonBuilder::new(ButtonStyle::Danger, "cancel-auto-create".into())
+                .label("Cancel".into())
+                .build(),
+        )
+        .build()
+}
+
+/// Discord renders relative timestamps as a live countdown.
+pub(super) fn countdown_content(due: i64) -> String {
+    format!(
+        "Issues will be created <t:{}:R> unless this is cancelled.",
+        due
+    )
+}
+
+/// Create the issues of a relayed message at the given unix timestamp.
+pub(super) fn schedule_issues(
+    state: &State,
+    message_id: Id<MessageMarker>,
+    due: i64,
+) -> Result<(), DatabaseError> {
+    state.database.write(|data| {
+        data.scheduled.insert(message_id.get(), due);
+    })?;
+
+    tokio::spawn(run_scheduled(Arc::clone(state), message_id, due));
+
+    Ok(())
+}
+
+/// Pick the schedules up again after a restart, overdue ones run right away.
+pub fn resume_scheduled(state: &State) {
+    let scheduled: Vec<(u64, i64)> = state.database.read(|data| {
+        data.scheduled
+            .iter()
+            .map(|(message_id, due)| (*message_id, *due))
+            .collect()
+    });
+
+    for (message_id, due) in scheduled {
+        tokio::spawn(run_scheduled(Arc::clone(state), Id::new(message_id), due));
+    }
+}
+
+/// Forget the schedule of a message, returns whether there was one.
+pub(super) fn unschedule(
+    state: &State,
+    message_id: Id<MessageMarker>,
+) -> Result<bool, DatabaseError> {
+    state
+        .database
+        .write(|data| data.scheduled.remove(&message_id.get()).is_some())
+}
+
+async fn run_scheduled(state: State, message_id: Id<MessageMarker>, due: i64) {
+    let wait = due - Utc::now().timestamp();
+    if wait > 0 {
+        tokio::time::sleep(Duration::from_secs(wait as u64)).await;
+    }
+
+    // Removing the schedule decides the race against someone pressing "Cancel" right now
+    match unschedule(&state, message_id) {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(err) => {
+            tracing::warn!("Could not remove the schedule of {}: {}", message_id, err);
+            return;
+        }
+    }
+
+    let result = async {
+        let channel_id = state
+            .database
+            .read(|data| data.channel_of(message_id.get()));
+        let message = state
+            .http
+            .message(channel_id, message_id)
+            .exec()
+            .recorded()
+            .await?
+            .model()
+            .await?;
+
+        // The guild could have given up the channel since, its issues are still due.
+        // Messages outside of any guild get the empty settings and so no issues.
+        let settings = match settings_of_channel(&state, channel_id) {
+            Some(settings) => settings,
+            None => {
+                let channel = state
+                    .http
+                    .channel(channel_id)
+                    .exec()
+                    .recorded()
+                    .await?
+                    .model()
+                    .await?;
+
+                settings_of_guild(&state, channel.guild_id)
+            }
+        };
+
+        auto_create_issues(&state, &settings, &message, None).await
+    };
+
+    if let Err(err) = result.await {
+        tracing::warn!(
+            "Could not create the scheduled issues of {}: {}",
+            message_id,
+            err
+        );
+    }
+}
+
+/// Veto the automatic issue creation and leave the message to be handled by hand.
+pub(super) async fn cancel_scheduled(
+    component: Box<MessageComponentInteraction>,
+    state: State,
+) -> HandlerResult {
+    let http = state.http.interaction(APPLICATION_ID);
+
+    if !unschedule(&state, component.message.id)? {
+        http.create_response(
+            component.id,
+            &component.token,
+            &ephemeral_response("The issues are not scheduled anymore."),
+        )
+        .exec()
+        .recorded()
+        .await?;
+
+        return Ok(());
+    }
+
+    let content = match component.author_id() {
+        Some(user_id) => format!("Automatic issue creation was cancelled by <@{}>.", user_id),
+        None => "Automatic issue creation was cancelled.".into(),
+    };
+
+    http.create_response(
+        component.id,
+        &component.token,
+        &InteractionResponse {
+            kind: InteractionResponseType::UpdateMessage,
+            data: Some(InteractionResponseData {
+                content: Some(content),
+                components: Some(relay_components()),
+                ..Default::default()
+            }),
+        },
+    )
+    .exec()
+    .recorded()
+    .await?;
+
+    Ok(())
+}
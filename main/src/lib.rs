@@ -1,3 +1,27 @@
+pub mod audit;
+pub mod classify;
+pub mod config;
 pub mod constants;
+pub mod database;
+pub mod digest;
+pub mod dry_run;
+pub mod embeds;
 pub mod github;
+pub mod github_auth;
+pub mod guilds;
+pub mod handlers;
+pub mod logging;
+pub mod metrics;
+pub mod monitor;
+pub mod process;
+pub mod replay;
+pub mod report;
+pub mod routes;
+pub mod shutdown;
+pub mod skip;
+pub mod threads;
+pub mod tracker;
+pub mod typediff;
 pub mod types;
+pub mod watcher;
+pub mod webhook;
@@ -0,0 +1,68 @@
+use std::env;
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{RollingFileAppender, Rotation},
+};
+use tracing_subscriber::{
+    fmt, layer::SubscriberExt, util::SubscriberInitExt, util::TryInitError, EnvFilter,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum LoggingError {
+    #[error("The environment variable {0} has to be one of {1}")]
+    Invalid(&'static str, &'static str),
+    #[error("Could not set up logging: {0}")]
+    Init(#[from] TryInitError),
+}
+
+/// Set up logging from `RUST_LOG`, `LOG_FORMAT`, `LOG_DIR` and `LOG_ROTATION`.
+///
+/// This reads its own environment variables instead of the `Config`, so errors in the
+/// config already get logged. The returned guard flushes the log file when dropped,
+/// so it has to live as long as the bot.
+pub fn init() -> Result<Option<WorkerGuard>, LoggingError> {
+    let json = match env::var("LOG_FORMAT").as_deref() {
+        Ok("json") => true,
+        Ok("text") | Err(_) => false,
+        Ok(_) => return Err(LoggingError::Invalid("LOG_FORMAT", "`text` or `json`")),
+    };
+
+    let (file, guard) = match env::var("LOG_DIR") {
+        Ok(dir) => {
+            let rotation = match env::var("LOG_ROTATION").as_deref() {
+                Ok("daily") | Err(_) => Rotation::DAILY,
+                Ok("hourly") => Rotation::HOURLY,
+                Ok("never") => Rotation::NEVER,
+                Ok(_) => {
+                    return Err(LoggingError::Invalid(
+                        "LOG_ROTATION",
+                        "`daily`, `hourly` or `never`",
+                    ))
+                }
+            };
+
+            let (writer, guard) =
+                tracing_appender::non_blocking(RollingFileAppender::new(rotation, dir, "bot.log"));
+            (Some(writer), Some(guard))
+        }
+        Err(_) => (None, None),
+    };
+
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let registry = tracing_subscriber::registry().with(filter);
+
+    // The formats are different types, so each needs its own subscriber
+    if json {
+        registry
+            .with(fmt::layer().json())
+            .with(file.map(|file| fmt::layer().json().with_writer(file)))
+            .try_init()?;
+    } else {
+        registry
+            .with(fmt::layer())
+            .with(file.map(|file| fmt::layer().with_ansi(false).with_writer(file)))
+            .try_init()?;
+    }
+
+    Ok(guard)
+}
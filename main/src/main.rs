@@ -1,56 +1,65 @@
 use bot::{
-    constants::{
-        API_DOCS_BOT_ID, API_DOCS_CHANNEL, ISSUE_BUTTON_EMOJI, ISSUE_MANAGEMENT_USERS,
-        REMOVE_BUTTON_EMOJI,
-    },
-    github::create_issues,
-    types::TwHttpClient,
+    config::Config,
+    constants::APPLICATION_ID,
+    database::Database,
+    digest::post_digests,
+    github_auth::GitHubAuth,
+    guilds::api_docs_guild,
+    handlers::{commands, handle_event, resume_jobs, resume_scheduled},
+    logging,
+    metrics::{Recorded, METRICS},
+    monitor, shutdown,
+    tracker::track_issues,
+    types::BotState,
+    watcher::watch_upstream,
+    webhook,
 };
 use dotenv::dotenv;
 use futures::stream::StreamExt;
-use std::{env, error::Error, sync::Arc};
-use twilight_gateway::{
-    cluster::{Cluster, ShardScheme},
-    Event,
+use std::{
+    env,
+    error::Error,
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
 };
+use tracing::{info, info_span, warn, Instrument};
+use twilight_gateway::cluster::{Cluster, ShardScheme};
 use twilight_http::Client as HttpClient;
-use twilight_model::{
-    application::{
-        callback::{CallbackData, InteractionResponse},
-        component::button::ButtonStyle,
-        interaction::Interaction,
-    },
-    channel::message::MessageFlags,
-    gateway::{payload::incoming::MessageCreate, Intents},
-    id::{ApplicationId, MessageId, UserId},
-};
-use util::builder::{ButtonBuilder, ComponentBuilder};
+use twilight_model::gateway::Intents;
 
 // TODO: look at this cool thing when its finished https://github.com/baptiste0928/twilight-interactions
 
-#[macro_use]
-extern crate log;
-
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
     // Load the .env file and just ignore any errors
     dotenv().ok();
-    env_logger::init();
+    let _log_guard = logging::init()?;
 
     info!("Starting up");
 
     let token = env::var("DISCORD_TOKEN")?;
+    let config = Config::from_env()?;
+    if config.dry_run {
+        info!("Running in dry-run mode, nothing is relayed or changed on GitHub");
+    }
+    let github = Arc::new(GitHubAuth::from_config(&config)?);
+    if let Some(app) = &config.github_app {
+        info!(app = app.app_id, "Authenticating as a GitHub App");
+    }
+    let database = Database::open(config.database_path.clone())?;
 
     // This is the default scheme. It will automatically create as many
     // shards as is suggested by Discord.
     let scheme = ShardScheme::Auto;
 
-    // Use intents to only receive guild message events.
-    let (cluster, mut events) =
-        Cluster::builder(token.to_owned(), Intents::GUILDS | Intents::GUILD_MESSAGES)
-            .shard_scheme(scheme)
-            .build()
-            .await?;
+    // Use intents to only receive guild message events. The content of messages is needed to
+    // mirror thread messages into their issues, it is a privileged intent which has to be
+    // enabled for the bot in the developer portal.
+    let intents = Intents::GUILDS | Intents::GUILD_MESSAGES | Intents::MESSAGE_CONTENT;
+    let (cluster, mut events) = Cluster::builder(token.to_owned(), intents)
+        .shard_scheme(scheme)
+        .build()
+        .await?;
     let cluster = Arc::new(cluster);
 
     // Start up the cluster.
@@ -63,209 +72,88 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
 
     // HTTP is separate from the gateway, so create a new client.
     let http = Arc::new(HttpClient::new(token));
-    http.set_application_id(
-        ApplicationId::new(906182472507740161_u64)
-            .expect("Could not create Application id for http"),
-    );
 
-    // Process each event as they come in.
-    while let Some((shard_id, event)) = events.next().await {
-        tokio::spawn(handle_event(shard_id, event, Arc::clone(&http)));
+    http.interaction(APPLICATION_ID)
+        .set_global_commands(&commands())
+        .exec()
+        .recorded()
+        .await?;
+
+    let state = Arc::new(BotState::new(
+        http,
+        Some(Arc::clone(&cluster)),
+        config,
+        database,
+        github,
+    ));
+
+    // Until it is set up, only the guild of the API docs channel keeps the default settings
+    if let Err(err) = api_docs_guild(&state).await {
+        warn!(
+            "Could not look up the guild of the API docs channel: {}",
+            err
+        );
     }
 
-    Ok(())
-}
-
-async fn handle_event(
-    shard_id: u64,
-    event: Event,
-    http: TwHttpClient,
-) -> Result<(), Box<dyn Error + Send + Sync>> {
-    match event {
-        Event::MessageCreate(mut message) => {
-            if message.content.starts_with("++fakeit")
-                && message.author.id == UserId::new(615542460151496705_u64).unwrap()
-            {
-                let id = if let Some(id) = message.content.split(' ').nth(1) {
-                    let raw = id.parse::<u64>()?;
-                    let id = MessageId::new(raw);
-                    if id.is_none() {
-                        return Ok(());
-                    }
+    tokio::spawn(track_issues(Arc::clone(&state)));
+    resume_scheduled(&state);
+    resume_jobs(&state);
 
-                    id.unwrap()
-                } else {
-                    return Ok(());
-                };
+    if let Some(poll_interval) = state.config.upstream_poll_interval {
+        tokio::spawn(watch_upstream(Arc::clone(&state), poll_interval));
+    }
 
-                let to_fake = http
-                    .message(API_DOCS_CHANNEL, id)
-                    .exec()
-                    .await?
-                    .model()
-                    .await?;
+    if let Some(channel_id) = state.config.digest_channel {
+        tokio::spawn(post_digests(Arc::clone(&state), channel_id));
+    }
 
-                message = Box::new(MessageCreate(to_fake));
-            }
+    if let Some(address) = state.config.metrics_address {
+        tokio::spawn(monitor::serve(
+            Arc::clone(&cluster),
+            address,
+            Arc::clone(&state.github),
+            state.config.issue_sync_interval,
+        ));
+    }
 
-            if message.channel_id == API_DOCS_CHANNEL || message.content.starts_with("++fakeit") {
-                // Messages could be send from someone else so check the author
-                if message.author.id != API_DOCS_BOT_ID {
-                    return Ok(());
-                }
+    if let Some(address) = state.config.webhook_address {
+        tokio::spawn(webhook::serve(Arc::clone(&state), address));
+    }
 
-                // It's possible that the commit has not been made to the `master` branch so check that
-                if let Some(embed) = message.embeds.first() {
-                    if let Some(title) = &embed.title {
-                        if !title.starts_with("[discord-api-docs:master]") {
-                            return Ok(());
+    let signal = shutdown::signal();
+    tokio::pin!(signal);
+
+    // Process each event as they come in, until a shutdown is requested.
+    loop {
+        tokio::select! {
+            event = events.next() => match event {
+                Some((shard_id, event)) => {
+                    METRICS.events_received.fetch_add(1, Ordering::Relaxed);
+
+                    let state = Arc::clone(&state);
+                    let span = info_span!("event", shard = shard_id, kind = ?event.kind());
+                    tokio::spawn(
+                        async move {
+                            if let Err(err) = handle_event(shard_id, event, state).await {
+                                warn!("Could not handle the event: {}", err);
+                            }
                         }
-                    }
-                } else {
-                    // Somehow no embed so just return
-                    return Ok(());
+                        .instrument(span),
+                    );
                 }
-
-                let components = ComponentBuilder::new()
-                    .button(
-                        ButtonBuilder::new(ButtonStyle::Primary, "create-github-issue".into())
-                            .emoji(ISSUE_BUTTON_EMOJI)
-                            .build(),
-                    )
-                    .button(
-                        ButtonBuilder::new(ButtonStyle::Secondary, "delete-message".into())
-                            .emoji(REMOVE_BUTTON_EMOJI)
-                            .build(),
-                    )
-                    .build();
-
-                http.create_message(API_DOCS_CHANNEL)
-                    .embeds(&message.embeds)?
-                    .components(&components)?
-                    .exec()
-                    .await?;
-
-                http.delete_message(API_DOCS_CHANNEL, message.id)
-                    .exec()
-                    .await?;
-            }
-        }
-        Event::ShardConnected(_) => {
-            println!("Connected on shard {}", shard_id);
+                None => break,
+            },
+            _ = &mut signal => break,
         }
-        Event::InteractionCreate(interaction) => {
-            if let Interaction::MessageComponent(component) = interaction.0 {
-                match component.data.custom_id.as_str() {
-                    "create-github-issue" => {
-                        let perms = match component.member {
-                            Some(member) if member.user.is_some() => {
-                                ISSUE_MANAGEMENT_USERS.contains(&member.user.unwrap().id)
-                            }
-                            _ => false,
-                        };
-
-                        if !perms {
-                            http.interaction_callback(
-                                component.id,
-                                &component.token,
-                                &InteractionResponse::ChannelMessageWithSource(CallbackData {
-                                    allowed_mentions: None,
-                                    components: None,
-                                    content: Some("You do not have access to this.".into()),
-                                    embeds: vec![],
-                                    flags: Some(MessageFlags::EPHEMERAL),
-                                    tts: None,
-                                }),
-                            )
-                            .exec()
-                            .await?;
-
-                            return Ok(());
-                        }
-
-                        // The message must have an embed at this point so its safe to use.
-                        if let Some(url) = &component.message.embeds[0].url {
-                            http.interaction_callback(
-                                component.id,
-                                &component.token,
-                                &InteractionResponse::DeferredUpdateMessage,
-                            )
-                            .exec()
-                            .await?;
-
-                            create_issues(url.clone()).await?;
-
-                            http.update_interaction_original(&component.token)?
-                                .components(Some(&[]))?
-                                .exec()
-                                .await?;
-
-                            return Ok(());
-                        }
-
-                        http.interaction_callback(
-                            component.id,
-                            &component.token,
-                            &InteractionResponse::ChannelMessageWithSource(CallbackData {
-                                allowed_mentions: None,
-                                components: None,
-                                content: Some("Could not get the issue link.".into()),
-                                embeds: vec![],
-                                flags: Some(MessageFlags::EPHEMERAL),
-                                tts: None,
-                            }),
-                        )
-                        .exec()
-                        .await?;
-                    }
-                    "delete-message" => {
-                        let perms = match component.member {
-                            Some(member) if member.user.is_some() => {
-                                ISSUE_MANAGEMENT_USERS.contains(&member.user.unwrap().id)
-                            }
-                            _ => false,
-                        };
+    }
 
-                        if !perms {
-                            http.interaction_callback(
-                                component.id,
-                                &component.token,
-                                &InteractionResponse::ChannelMessageWithSource(CallbackData {
-                                    allowed_mentions: None,
-                                    components: None,
-                                    content: Some("You do not have access to this.".into()),
-                                    embeds: vec![],
-                                    flags: Some(MessageFlags::EPHEMERAL),
-                                    tts: None,
-                                }),
-                            )
-                            .exec()
-                            .await?;
+    info!("Shutting down");
 
-                            return Ok(());
-                        }
+    // No new interactions come in from here on, running jobs stop at their next checkpoint
+    cluster.down();
+    shutdown::drain(&state, Duration::from_secs(30)).await;
 
-                        http.interaction_callback(
-                            component.id,
-                            &component.token,
-                            &InteractionResponse::UpdateMessage(CallbackData {
-                                allowed_mentions: None,
-                                components: Some(vec![]),
-                                content: None,
-                                embeds: vec![],
-                                flags: None,
-                                tts: None,
-                            }),
-                        )
-                        .exec()
-                        .await?;
-                    }
-                    _ => {}
-                }
-            }
-        }
-        _ => {}
-    }
+    info!("Shut down");
 
     Ok(())
 }
@@ -0,0 +1,273 @@
+use crate::process::ProcessStats;
+use std::{
+    fmt::{Display, Write},
+    future::Future,
+    sync::atomic::{AtomicI64, AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+use twilight_gateway::{cluster::Cluster, shard::Stage};
+use twilight_http::{
+    response::{Response, ResponseFuture},
+    Error as HttpError,
+};
+
+/// Counters of everything the bot does, exposed on `/metrics`.
+pub static METRICS: Metrics = Metrics::new();
+
+/// Requests to one API, the latencies are exported as a Prometheus summary.
+pub struct ApiMetrics {
+    requests: AtomicU64,
+    errors: AtomicU64,
+    latency_micros: AtomicU64,
+}
+
+impl ApiMetrics {
+    const fn new() -> Self {
+        Self {
+            requests: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            latency_micros: AtomicU64::new(0),
+        }
+    }
+
+    pub fn record(&self, latency: Duration, failed: bool) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.latency_micros
+            .fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+        if failed {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Record Discord requests like [`crate::github::send`] does for GitHub ones,
+/// by awaiting them with `.exec().recorded().await`.
+pub trait Recorded<T> {
+    fn recorded(self) -> impl Future<Output = Result<Response<T>, HttpError>> + Send;
+}
+
+impl<T: Send + Unpin> Recorded<T> for ResponseFuture<T> {
+    async fn recorded(self) -> Result<Response<T>, HttpError> {
+        let started = Instant::now();
+        let result = self.await;
+
+        METRICS.discord.record(started.elapsed(), result.is_err());
+
+        result
+    }
+}
+
+pub struct Metrics {
+    pub events_received: AtomicU64,
+    pub relays: AtomicU64,
+    pub issues_created: AtomicU64,
+    pub github: ApiMetrics,
+    /// Requests over HTTP, the gateway latency of the shards is exported separately.
+    pub discord: ApiMetrics,
+    /// -1 until the first GitHub response came in.
+    github_rate_limit_remaining: AtomicI64,
+}
+
+impl Metrics {
+    const fn new() -> Self {
+        Self {
+            events_received: AtomicU64::new(0),
+            relays: AtomicU64::new(0),
+            issues_created: AtomicU64::new(0),
+            github: ApiMetrics::new(),
+            discord: ApiMetrics::new(),
+            github_rate_limit_remaining: AtomicI64::new(-1),
+        }
+    }
+
+    pub fn set_github_rate_limit(&self, remaining: i64) {
+        self.github_rate_limit_remaining
+            .store(remaining, Ordering::Relaxed);
+    }
+
+    pub fn github_rate_limit(&self) -> Option<i64> {
+        match self.github_rate_limit_remaining.load(Ordering::Relaxed) {
+            -1 => None,
+            remaining => Some(remaining),
+        }
+    }
+
+    /// Render everything in the Prometheus text format.
+    pub async fn render(&self, cluster: &Cluster) -> String {
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "bot_events_received_total",
+            "counter",
+            "Gateway events received.",
+        );
+        sample(
+            &mut out,
+            "bot_events_received_total",
+            "",
+            self.events_received.load(Ordering::Relaxed),
+        );
+        header(
+            &mut out,
+            "bot_relays_total",
+            "counter",
+            "Pushes relayed into the API docs channel.",
+        );
+        sample(
+            &mut out,
+            "bot_relays_total",
+            "",
+            self.relays.load(Ordering::Relaxed),
+        );
+        header(
+            &mut out,
+            "bot_issues_created_total",
+            "counter",
+            "Issues created on GitHub.",
+        );
+        sample(
+            &mut out,
+            "bot_issues_created_total",
+            "",
+            self.issues_created.load(Ordering::Relaxed),
+        );
+
+        header(
+            &mut out,
+            "bot_api_requests_total",
+            "counter",
+            "Requests sent to an API.",
+        );
+        for (api, metrics) in [("github", &self.github), ("discord", &self.discord)] {
+            sample(
+                &mut out,
+                "bot_api_requests_total",
+                &format!("{{api=\"{}\"}}", api),
+                metrics.requests.load(Ordering::Relaxed),
+            );
+        }
+        header(
+            &mut out,
+            "bot_api_errors_total",
+            "counter",
+            "Failed requests to an API.",
+        );
+        for (api, metrics) in [("github", &self.github), ("discord", &self.discord)] {
+            sample(
+                &mut out,
+                "bot_api_errors_total",
+                &format!("{{api=\"{}\"}}", api),
+                metrics.errors.load(Ordering::Relaxed),
+            );
+        }
+
+        for (api, name, metrics) in [
+            ("github", "GitHub", &self.github),
+            ("discord", "Discord", &self.discord),
+        ] {
+            header(
+                &mut out,
+                &format!("bot_{}_request_seconds", api),
+                "summary",
+                &format!("Latency of {} API requests.", name),
+            );
+            sample(
+                &mut out,
+                &format!("bot_{}_request_seconds_sum", api),
+                "",
+                metrics.latency_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0,
+            );
+            sample(
+                &mut out,
+                &format!("bot_{}_request_seconds_count", api),
+                "",
+                metrics.requests.load(Ordering::Relaxed),
+            );
+        }
+
+        if let Some(remaining) = self.github_rate_limit() {
+            header(
+                &mut out,
+                "bot_github_rate_limit_remaining",
+                "gauge",
+                "Requests left in the current GitHub rate limit window.",
+            );
+            sample(&mut out, "bot_github_rate_limit_remaining", "", remaining);
+        }
+
+        let shards = cluster.info();
+        header(
+            &mut out,
+            "bot_shard_connected",
+            "gauge",
+            "Whether a gateway shard is connected.",
+        );
+        for (id, info) in &shards {
+            sample(
+                &mut out,
+                "bot_shard_connected",
+                &format!("{{shard=\"{}\"}}", id),
+                u8::from(info.stage() == Stage::Connected),
+            );
+        }
+        header(
+            &mut out,
+            "bot_shard_latency_seconds",
+            "gauge",
+            "Average gateway heartbeat latency of a shard.",
+        );
+        for (id, info) in &shards {
+            if let Some(latency) = info.latency().average() {
+                sample(
+                    &mut out,
+                    "bot_shard_latency_seconds",
+                    &format!("{{shard=\"{}\"}}", id),
+                    latency.as_secs_f64(),
+                );
+            }
+        }
+
+        match ProcessStats::get() {
+            Ok(stats) => {
+                header(
+                    &mut out,
+                    "process_cpu_seconds_total",
+                    "counter",
+                    "CPU time spent by the process.",
+                );
+                sample(
+                    &mut out,
+                    "process_cpu_seconds_total",
+                    "",
+                    stats.cpu_time.as_secs_f64(),
+                );
+                header(
+                    &mut out,
+                    "process_resident_memory_bytes",
+                    "gauge",
+                    "Memory used by the process.",
+                );
+                sample(
+                    &mut out,
+                    "process_resident_memory_bytes",
+                    "",
+                    stats.resident_memory_bytes,
+                );
+            }
+            Err(err) => tracing::warn!("Could not read the process stats: {}", err),
+        }
+
+        out
+    }
+}
+
+// Writing into a `String` can not fail
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}\n# TYPE {} {}", name, help, name, kind);
+}
+
+fn sample(out: &mut String, name: &str, labels: &str, value: impl Display) {
+    let _ = writeln!(out, "{}{} {}", name, labels, value);
+}
@@ -0,0 +1,112 @@
+use crate::{github::check_token, github_auth::GitHubAuth, metrics::METRICS};
+use axum::{extract::Extension, http::StatusCode, routing::get, Json, Router};
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use twilight_gateway::{cluster::Cluster, shard::Stage};
+
+/// The last check of the GitHub token, so frequent health probes do not use up the rate limit.
+struct TokenCheck {
+    auth: Arc<GitHubAuth>,
+    max_age: Duration,
+    /// When the token was checked and whether it was valid, `None` if GitHub was unreachable.
+    last: Mutex<Option<(Instant, Option<bool>)>>,
+}
+
+impl TokenCheck {
+    async fn token_valid(&self) -> Option<bool> {
+        if let Some((checked_at, valid)) = *self.last.lock() {
+            if checked_at.elapsed() < self.max_age {
+                return valid;
+            }
+        }
+
+        let valid = match check_token(&self.auth).await {
+            Ok(valid) => Some(valid),
+            Err(err) => {
+                tracing::warn!("Could not check the GitHub token: {}", err);
+                None
+            }
+        };
+        *self.last.lock() = Some((Instant::now(), valid));
+
+        valid
+    }
+}
+
+/// Serve `/healthz` and the Prometheus `/metrics` for monitoring the bot.
+///
+/// The GitHub token is checked at most once per `token_check_interval`.
+pub async fn serve(
+    cluster: Arc<Cluster>,
+    address: SocketAddr,
+    auth: Arc<GitHubAuth>,
+    token_check_interval: Duration,
+) {
+    let token_check = Arc::new(TokenCheck {
+        auth,
+        max_age: token_check_interval,
+        last: Mutex::new(None),
+    });
+    let app = Router::new()
+        .route("/healthz", get(health))
+        .route("/metrics", get(metrics))
+        .layer(Extension(cluster))
+        .layer(Extension(token_check));
+
+    tracing::info!("Serving health and metrics on {}", address);
+
+    if let Err(err) = axum::Server::bind(&address)
+        .serve(app.into_make_service())
+        .await
+    {
+        tracing::error!("The health and metrics server stopped: {}", err);
+    }
+}
+
+/// Healthy while every shard is connected and GitHub accepts the token.
+async fn health(
+    Extension(cluster): Extension<Arc<Cluster>>,
+    Extension(token_check): Extension<Arc<TokenCheck>>,
+) -> (StatusCode, Json<Value>) {
+    let mut shards: Vec<_> = cluster.info().into_iter().collect();
+    shards.sort_by_key(|(id, _)| *id);
+
+    let connected = !shards.is_empty()
+        && shards
+            .iter()
+            .all(|(_, info)| info.stage() == Stage::Connected);
+
+    let token_valid = token_check.token_valid().await;
+
+    let status = if connected && token_valid == Some(true) {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    let body = json!({
+        "healthy": status == StatusCode::OK,
+        "shards": shards
+            .iter()
+            .map(|(id, info)| json!({
+                "id": id,
+                "stage": info.stage().to_string(),
+                "latency_ms": info.latency().average().map(|latency| latency.as_millis() as u64),
+            }))
+            .collect::<Vec<_>>(),
+        // `null` if GitHub could not be reached
+        "github_token_valid": token_valid,
+        "github_rate_limit_remaining": METRICS.github_rate_limit(),
+    });
+
+    (status, Json(body))
+}
+
+async fn metrics(Extension(cluster): Extension<Arc<Cluster>>) -> String {
+    METRICS.render(&cluster).await
+}
@@ -0,0 +1,44 @@
+use std::{fs, io, time::Duration};
+
+/// Linux counts CPU time in clock ticks, which are a hundredth of a second on every common setup.
+const CLOCK_TICKS_PER_SEC: u64 = 100;
+
+/// What the bot process uses of the machine, read from `/proc` so only available on Linux.
+pub struct ProcessStats {
+    pub resident_memory_bytes: u64,
+    /// User and kernel time together.
+    pub cpu_time: Duration,
+}
+
+impl ProcessStats {
+    pub fn get() -> io::Result<Self> {
+        let invalid = |what: &str| io::Error::new(io::ErrorKind::InvalidData, what);
+
+        // Like `VmRSS:    12345 kB`
+        let status = fs::read_to_string("/proc/self/status")?;
+        let resident_kib: u64 = status
+            .lines()
+            .find_map(|line| line.strip_prefix("VmRSS:"))
+            .and_then(|value| value.trim().strip_suffix("kB"))
+            .and_then(|value| value.trim().parse().ok())
+            .ok_or_else(|| invalid("/proc/self/status has no resident memory"))?;
+
+        // The name in the second field can contain spaces, the fields after it can not
+        let stat = fs::read_to_string("/proc/self/stat")?;
+        let fields: Vec<&str> = stat
+            .rsplit_once(')')
+            .map(|(_, fields)| fields.split_whitespace().collect())
+            .unwrap_or_default();
+        // utime and stime are the 14th and 15th field, the first two were cut off
+        let ticks = |index: usize| -> Option<u64> { fields.get(index)?.parse().ok() };
+        let cpu_ticks = ticks(11)
+            .zip(ticks(12))
+            .map(|(user, kernel)| user + kernel)
+            .ok_or_else(|| invalid("/proc/self/stat has no CPU times"))?;
+
+        Ok(Self {
+            resident_memory_bytes: resident_kib * 1024,
+            cpu_time: Duration::from_millis(cpu_ticks * 1000 / CLOCK_TICKS_PER_SEC),
+        })
+    }
+}
@@ -0,0 +1,203 @@
+use crate::{
+    database::GuildSettings,
+    embeds::compare_embed,
+    github::fetch_compare,
+    handlers::{relay_webhook_message, route_relay, webhook_push},
+    metrics::Recorded,
+    routes::{route_push, BranchAction},
+    types::State,
+};
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+use twilight_model::{
+    channel::Message,
+    id::{
+        marker::{ChannelMarker, MessageMarker},
+        Id,
+    },
+};
+
+/// Most messages a single replay goes through, so a mistyped range can not flood the channel.
+pub const MAX_REPLAY_MESSAGES: usize = 200;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayError {
+    #[error("The first message of the range comes after the last one.")]
+    ReversedRange,
+    #[error("The range has more than {} messages.", MAX_REPLAY_MESSAGES)]
+    TooManyMessages,
+    #[error("The guild has no relay channel to replay.")]
+    NoRelayChannel,
+}
+
+/// What to push through the relay again.
+#[derive(Debug, Clone)]
+pub enum ReplaySource {
+    /// The pushes the GitHub webhook integration posted into the relay channel of the guild,
+    /// from `first` up to and including `last`.
+    Messages {
+        first: Id<MessageMarker>,
+        last: Id<MessageMarker>,
+    },
+    /// Everything pushed to a branch after `base` up to and including `head`, relayed as one push.
+    Commits {
+        repo: String,
+        branch: String,
+        base: String,
+        head: String,
+    },
+}
+
+/// A push of a replay and what the routes do with it.
+#[derive(Debug, Clone)]
+pub struct ReplayedPush {
+    pub repo: String,
+    pub branch: String,
+    pub title: String,
+    /// The webhook message the push was read from, `None` for backfilled commits.
+    pub message_id: Option<Id<MessageMarker>>,
+    pub action: BranchAction,
+    /// Whether the push was relayed, never set for dry runs.
+    pub relayed: bool,
+}
+
+impl Display for ReplayedPush {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "`{}:{}` {}", self.repo, self.branch, self.title)?;
+        if let Some(message_id) = self.message_id {
+            write!(f, " (message {})", message_id)?;
+        }
+
+        match (self.action, self.relayed) {
+            (BranchAction::Ignore, _) => write!(f, ": ignored"),
+            (action, true) => write!(f, ": relayed as {:?}", action),
+            (action, false) => write!(f, ": would be relayed as {:?}", action),
+        }
+    }
+}
+
+/// Push messages or commits through the relay again, like they just came in.
+///
+/// With `dry_run` nothing is posted or deleted, only what would be relayed is returned.
+pub async fn replay(
+    state: &State,
+    settings: &GuildSettings,
+    source: &ReplaySource,
+    dry_run: bool,
+) -> Result<Vec<ReplayedPush>, Box<dyn Error + Send + Sync>> {
+    match source {
+        ReplaySource::Messages { first, last } => {
+            let channel_id = settings
+                .relay_channel
+                .map(Id::new)
+                .ok_or(ReplayError::NoRelayChannel)?;
+            let messages = fetch_range(state, channel_id, *first, *last).await?;
+
+            let mut pushes = vec![];
+            for message in &messages {
+                let (repo, branch) = match webhook_push(settings, message) {
+                    Some(target) => target,
+                    None => continue,
+                };
+                let push = ReplayedPush {
+                    repo: repo.to_owned(),
+                    branch: branch.to_owned(),
+                    title: message
+                        .embeds
+                        .first()
+                        .and_then(|embed| embed.title.clone())
+                        .unwrap_or_default(),
+                    message_id: Some(message.id),
+                    action: action_of(settings, repo, branch),
+                    relayed: !dry_run && relay_webhook_message(state, settings, message).await?,
+                };
+
+                tracing::info!(dry_run, "Replayed {}", push);
+                pushes.push(push);
+            }
+
+            Ok(pushes)
+        }
+        ReplaySource::Commits {
+            repo,
+            branch,
+            base,
+            head,
+        } => {
+            let compare = fetch_compare(&state.github, repo, base, head).await?;
+            let embed = match compare_embed(repo, branch, &compare)? {
+                Some(embed) => embed,
+                None => return Ok(vec![]),
+            };
+
+            let push = ReplayedPush {
+                repo: repo.clone(),
+                branch: branch.clone(),
+                title: embed.title.clone().unwrap_or_default(),
+                message_id: None,
+                action: action_of(settings, repo, branch),
+                relayed: !dry_run && route_relay(state, settings, repo, branch, &[embed]).await?,
+            };
+
+            tracing::info!(dry_run, "Backfilled {}", push);
+
+            Ok(vec![push])
+        }
+    }
+}
+
+fn action_of(settings: &GuildSettings, repo: &str, branch: &str) -> BranchAction {
+    route_push(&settings.routes, repo, branch).map_or(BranchAction::Ignore, |(_, action)| action)
+}
+
+/// Fetch the messages of a channel from `first` up to and including `last`, oldest first.
+async fn fetch_range(
+    state: &State,
+    channel_id: Id<ChannelMarker>,
+    first: Id<MessageMarker>,
+    last: Id<MessageMarker>,
+) -> Result<Vec<Message>, Box<dyn Error + Send + Sync>> {
+    if first > last {
+        return Err(ReplayError::ReversedRange.into());
+    }
+
+    let mut messages = vec![
+        state
+            .http
+            .message(channel_id, first)
+            .exec()
+            .recorded()
+            .await?
+            .model()
+            .await?,
+    ];
+
+    let mut after = first;
+    while after < last {
+        let mut batch = state
+            .http
+            .channel_messages(channel_id)
+            .after(after)
+            .limit(100)?
+            .exec()
+            .recorded()
+            .await?
+            .models()
+            .await?;
+
+        batch.sort_by_key(|message| message.id);
+        after = match batch.last() {
+            Some(message) => message.id,
+            None => break,
+        };
+
+        messages.extend(batch.into_iter().filter(|message| message.id <= last));
+        if messages.len() > MAX_REPLAY_MESSAGES {
+            return Err(ReplayError::TooManyMessages.into());
+        }
+    }
+
+    Ok(messages)
+}
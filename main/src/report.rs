@@ -0,0 +1,327 @@
+use crate::{
+    classify::ChangeSummary,
+    constants::API_DOCS_REPO,
+    github::{fetch_compare, CreateIssuesError},
+    github_auth::GitHubAuth,
+    types::github::GitHubCompare,
+};
+
+/// The changes of a single docs page between two refs.
+#[derive(Debug, Clone)]
+pub struct PageReport {
+    pub filename: String,
+    /// `added`, `modified`, `removed` or `renamed`, as GitHub reports it.
+    pub status: String,
+    pub additions: i64,
+    pub deletions: i64,
+    pub changes: ChangeSummary,
+}
+
+/// GitHub lists at most this many files in a compare, and no page has the others.
+const MAX_COMPARE_FILES: usize = 300;
+
+/// Everything which changed in the docs between two refs.
+#[derive(Debug, Clone)]
+pub struct DocsReport {
+    pub from: String,
+    pub to: String,
+    pub html_url: String,
+    pub total_commits: i64,
+    pub pages: Vec<PageReport>,
+    /// What GitHub left out of a large compare, the report is incomplete unless this is empty.
+    pub omissions: Vec<String>,
+}
+
+/// Compare two refs of the API docs, like the tag of the last release and `master`.
+pub async fn diff_docs(
+    auth: &GitHubAuth,
+    from: &str,
+    to: &str,
+) -> Result<DocsReport, CreateIssuesError> {
+    let compare = fetch_compare(auth, API_DOCS_REPO, from, to).await?;
+
+    Ok(DocsReport::from_compare(from, to, &compare))
+}
+
+impl DocsReport {
+    pub fn from_compare(from: &str, to: &str, compare: &GitHubCompare) -> Self {
+        let pages = compare
+            .files
+            .iter()
+            .filter(|file| file.filename.ends_with(".md") || file.filename.ends_with(".mdx"))
+            .map(|file| PageReport {
+                filename: file.filename.clone(),
+                status: file.status.clone(),
+                additions: file.additions,
+                deletions: file.deletions,
+                changes: ChangeSummary::analyze(std::slice::from_ref(file)),
+            })
+            .collect();
+
+        Self {
+            from: from.to_owned(),
+            to: to.to_owned(),
+            html_url: compare.html_url.clone(),
+            total_commits: compare.total_commits,
+            pages,
+            omissions: omissions(compare),
+        }
+    }
+
+    /// How many changes of every kind were found over all pages.
+    pub fn totals(&self) -> Vec<(&'static str, usize)> {
+        let mut totals: Vec<(&'static str, usize)> = vec![];
+
+        for (title, entries) in self.pages.iter().flat_map(|page| page.changes.sections()) {
+            match totals.iter_mut().find(|(total, _)| *total == title) {
+                Some((_, count)) => *count += entries.len(),
+                None => totals.push((title, entries.len())),
+            }
+        }
+
+        totals
+    }
+
+    pub fn is_breaking(&self) -> bool {
+        self.pages.iter().any(|page| page.changes.is_breaking())
+    }
+
+    /// Render the whole report as a Markdown document.
+    pub fn to_markdown(&self) -> String {
+        let mut lines = vec![
+            format!(
+                "# Discord API docs changes from `{}` to `{}`",
+                self.from, self.to
+            ),
+            String::new(),
+            format!(
+                "{} commits, {} changed pages: {}",
+                self.total_commits,
+                self.pages.len(),
+                self.html_url
+            ),
+        ];
+
+        if !self.omissions.is_empty() {
+            lines.push(String::new());
+            lines.push("**This report is incomplete:**".into());
+            lines.extend(
+                self.omissions
+                    .iter()
+                    .map(|omission| format!("- {}", omission)),
+            );
+        }
+
+        let totals = self.totals();
+        if !totals.is_empty() {
+            lines.push(String::new());
+            lines.push("## Summary".into());
+            lines.extend(
+                totals
+                    .iter()
+                    .map(|(title, count)| format!("- {}: {}", title, count)),
+            );
+        }
+
+        for page in &self.pages {
+            lines.push(String::new());
+            lines.push(format!("## {}", page.filename));
+            lines.push(format!(
+                "{}, +{} -{}",
+                page.status, page.additions, page.deletions
+            ));
+
+            for (title, entries) in page.changes.sections() {
+                lines.push(String::new());
+                lines.push(format!("### {}", title));
+                lines.extend(entries.into_iter().map(|entry| format!("- {}", entry)));
+            }
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// What GitHub left out of a compare, its commits and files are cut off and the patches
+/// of large files are missing.
+fn omissions(compare: &GitHubCompare) -> Vec<String> {
+    let mut omissions = vec![];
+
+    if (compare.commits.len() as i64) < compare.total_commits {
+        omissions.push(format!(
+            "Only {} of the {} commits are listed",
+            compare.commits.len(),
+            compare.total_commits
+        ));
+    }
+    if compare.files.len() >= MAX_COMPARE_FILES {
+        omissions.push(format!(
+            "Only the first {} changed files are listed",
+            compare.files.len()
+        ));
+    }
+
+    let without_patch: Vec<_> = compare
+        .files
+        .iter()
+        .filter(|file| file.filename.ends_with(".md") || file.filename.ends_with(".mdx"))
+        .filter(|file| file.patch.is_empty() && file.changes > 0)
+        .map(|file| format!("`{}`", file.filename))
+        .collect();
+    if !without_patch.is_empty() {
+        omissions.push(format!(
+            "The changes of {} are too large to be detected",
+            without_patch.join(", ")
+        ));
+    }
+
+    omissions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::github::{BaseCommit, File};
+
+    const NEW_FIELD: &str = "@@ -30,6 +30,7 @@
+ ###### Channel Structure
+ 
+ | Field | Type | Description |
+ |-------|------|-------------|
++| default_forum_layout? | integer | the default forum layout |
+ | type | integer | the type of channel |";
+
+    const REMOVED_FIELD: &str = "@@ -60,4 +60,3 @@
+ ###### Guild Structure
+ 
+-| max_presences? | ?integer | the maximum number of presences for the guild |
+ | max_members? | integer | the maximum number of members for the guild |";
+
+    fn file(filename: &str, patch: &str) -> File {
+        File {
+            filename: filename.into(),
+            status: "modified".into(),
+            additions: 1,
+            deletions: 1,
+            changes: 2,
+            patch: patch.into(),
+            ..Default::default()
+        }
+    }
+
+    fn compare(files: Vec<File>) -> GitHubCompare {
+        GitHubCompare {
+            html_url: "https://github.com/discord/discord-api-docs/compare/v10...master".into(),
+            total_commits: 2,
+            commits: vec![BaseCommit::default(); 2],
+            files,
+            ..Default::default()
+        }
+    }
+
+    fn report(files: Vec<File>) -> DocsReport {
+        DocsReport::from_compare("v10", "master", &compare(files))
+    }
+
+    #[test]
+    fn only_docs_pages_are_reported() {
+        let report = report(vec![
+            file("docs/resources/Channel.md", NEW_FIELD),
+            file("docs/topics/Gateway.mdx", ""),
+            file("package.json", "@@ -1 +1 @@\n-a\n+b"),
+        ]);
+
+        let filenames: Vec<_> = report
+            .pages
+            .iter()
+            .map(|page| page.filename.as_str())
+            .collect();
+        assert_eq!(
+            filenames,
+            ["docs/resources/Channel.md", "docs/topics/Gateway.mdx"]
+        );
+        assert_eq!(report.pages[0].changes.new_fields.len(), 1);
+        assert!(report.pages[1].changes.is_empty());
+        assert_eq!(report.total_commits, 2);
+        assert!(!report.is_breaking());
+    }
+
+    #[test]
+    fn totals_add_up_the_pages() {
+        let report = report(vec![
+            file("docs/resources/Channel.md", NEW_FIELD),
+            file("docs/resources/Thread.md", NEW_FIELD),
+            file("docs/resources/Guild.md", REMOVED_FIELD),
+        ]);
+
+        assert_eq!(report.totals(), [("New fields", 2), ("Removed fields", 1)]);
+        assert!(report.is_breaking());
+        assert!(DocsReport::from_compare("a", "b", &compare(vec![]))
+            .totals()
+            .is_empty());
+    }
+
+    #[test]
+    fn markdown_lists_every_page() {
+        let markdown = report(vec![
+            file("docs/resources/Channel.md", NEW_FIELD),
+            file("docs/topics/Gateway.mdx", "@@ -1 +1 @@\n-a\n+b"),
+        ])
+        .to_markdown();
+
+        assert_eq!(
+            markdown,
+            "# Discord API docs changes from `v10` to `master`
+
+2 commits, 2 changed pages: https://github.com/discord/discord-api-docs/compare/v10...master
+
+## Summary
+- New fields: 1
+
+## docs/resources/Channel.md
+modified, +1 -1
+
+### New fields
+- `default_forum_layout?` in Channel Structure
+
+## docs/topics/Gateway.mdx
+modified, +1 -1"
+        );
+    }
+
+    #[test]
+    fn complete_compares_have_no_omissions() {
+        let report = report(vec![file("docs/resources/Channel.md", NEW_FIELD)]);
+
+        assert!(report.omissions.is_empty());
+        assert!(!report.to_markdown().contains("incomplete"));
+    }
+
+    #[test]
+    fn cut_off_compares_are_marked_incomplete() {
+        let mut files = vec![file("docs/resources/Channel.md", ""); MAX_COMPARE_FILES];
+        files[1].filename = "docs/resources/Guild.md".into();
+        files[2].changes = 0;
+        let mut compare = compare(files);
+        compare.total_commits = 400;
+        compare.commits = vec![BaseCommit::default(); 250];
+
+        let report = DocsReport::from_compare("v9", "v10", &compare);
+
+        assert_eq!(report.omissions.len(), 3);
+        assert_eq!(
+            report.omissions[0],
+            "Only 250 of the 400 commits are listed"
+        );
+        assert_eq!(
+            report.omissions[1],
+            "Only the first 300 changed files are listed"
+        );
+        assert!(report.omissions[2].starts_with(
+            "The changes of `docs/resources/Channel.md`, `docs/resources/Guild.md`, "
+        ));
+        assert!(report.to_markdown().contains(
+            "**This report is incomplete:**\n- Only 250 of the 400 commits are listed\n"
+        ));
+    }
+}
@@ -0,0 +1,282 @@
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{fs, io, path::Path};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RoutesError {
+    #[error("Error while reading the routes file: {0}")]
+    Io(#[from] io::Error),
+    #[error("Error while parsing the routes file: {0}")]
+    Serde(#[from] serde_json::Error),
+}
+
+/// What happens with a push to a branch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BranchAction {
+    /// Relay the push with the issue buttons.
+    Relay,
+    /// Relay the push without any buttons.
+    RelayReadOnly,
+    /// Relay the push and create the issues right away.
+    AutoCreate,
+    /// Do not relay the push at all.
+    Ignore,
+}
+
+/// A branch name pattern, either a glob (`*` and `?`, a star also matches slashes) or a regex
+/// wrapped in slashes (`/^v\d+$/`).
+#[derive(Debug, Clone)]
+pub enum BranchPattern {
+    Glob(String),
+    Regex(Regex),
+}
+
+impl BranchPattern {
+    pub fn matches(&self, branch: &str) -> bool {
+        match self {
+            Self::Glob(glob) => glob_matches(glob.as_bytes(), branch.as_bytes()),
+            Self::Regex(regex) => regex.is_match(branch),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for BranchPattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+
+        match raw
+            .strip_prefix('/')
+            .and_then(|pattern| pattern.strip_suffix('/'))
+        {
+            Some(pattern) => Regex::new(pattern)
+                .map(Self::Regex)
+                .map_err(serde::de::Error::custom),
+            None => Ok(Self::Glob(raw)),
+        }
+    }
+}
+
+impl Serialize for BranchPattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Glob(glob) => serializer.serialize_str(glob),
+            Self::Regex(regex) => serializer.serialize_str(&format!("/{}/", regex.as_str())),
+        }
+    }
+}
+
+/// Match a glob without recursion, a star only ever goes back to the last star so patterns
+/// like `a*a*a*b` stay linear.
+fn glob_matches(glob: &[u8], text: &[u8]) -> bool {
+    let (mut glob_index, mut text_index) = (0, 0);
+    // The last star and how much of the text it swallowed so far
+    let mut star = None;
+
+    while text_index < text.len() {
+        match glob.get(glob_index) {
+            Some(b'*') => {
+                star = Some((glob_index, text_index));
+                glob_index += 1;
+            }
+            Some(&expected) if expected == b'?' || expected == text[text_index] => {
+                glob_index += 1;
+                text_index += 1;
+            }
+            _ => match star {
+                Some((star_index, swallowed)) => {
+                    star = Some((star_index, swallowed + 1));
+                    glob_index = star_index + 1;
+                    text_index = swallowed + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    glob[glob_index..].iter().all(|&byte| byte == b'*')
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BranchRule {
+    pub pattern: BranchPattern,
+    pub action: BranchAction,
+}
+
+/// Where pushes of an upstream repo go and what happens with them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Route {
+    pub name: String,
+    /// The repo in the `owner/repo` format.
+    pub repo: String,
+    /// The first matching rule decides what happens, branches without a match are ignored.
+    pub branches: Vec<BranchRule>,
+    /// The color of the relayed embeds as a hex code like `#5865f2`, the GitHub push color if not set.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_color",
+        serialize_with = "serialize_color",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub color: Option<u32>,
+}
+
+fn deserialize_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u32>, D::Error> {
+    let raw = String::deserialize(deserializer)?;
+
+    u32::from_str_radix(raw.trim_start_matches('#'), 16)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+fn serialize_color<S: Serializer>(color: &Option<u32>, serializer: S) -> Result<S::Ok, S::Error> {
+    match color {
+        Some(color) => serializer.serialize_str(&format!("#{:06x}", color)),
+        None => serializer.serialize_none(),
+    }
+}
+
+impl Route {
+    /// Whether the route is for the given repo, the embeds of the Discord webhook
+    /// integration only contain the repo name without the owner.
+    pub fn is_for(&self, repo: &str) -> bool {
+        self.repo == repo || self.repo.rsplit('/').next() == Some(repo)
+    }
+
+    pub fn action_for(&self, branch: &str) -> BranchAction {
+        self.branches
+            .iter()
+            .find(|rule| rule.pattern.matches(branch))
+            .map_or(BranchAction::Ignore, |rule| rule.action)
+    }
+}
+
+/// The routes used when no routes file exists, this is how the bot always behaved.
+pub fn default_routes() -> Vec<Route> {
+    vec![Route {
+        name: "discord-api-docs".into(),
+        repo: "discord/discord-api-docs".into(),
+        branches: vec![BranchRule {
+            pattern: BranchPattern::Glob("master".into()),
+            action: BranchAction::Relay,
+        }],
+        color: None,
+    }]
+}
+
+pub fn load_routes(path: &Path) -> Result<Vec<Route>, RoutesError> {
+    match fs::read(path) {
+        Ok(raw) => Ok(serde_json::from_slice(&raw)?),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(default_routes()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Find the route and the action for a push.
+pub fn route_push<'a>(
+    routes: &'a [Route],
+    repo: &str,
+    branch: &str,
+) -> Option<(&'a Route, BranchAction)> {
+    routes
+        .iter()
+        .find(|route| route.is_for(repo))
+        .map(|route| (route, route.action_for(branch)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glob(pattern: &str) -> BranchPattern {
+        BranchPattern::Glob(pattern.into())
+    }
+
+    #[test]
+    fn glob_without_wildcards_matches_exactly() {
+        assert!(glob("master").matches("master"));
+        assert!(!glob("master").matches("master2"));
+        assert!(!glob("master").matches("maste"));
+        assert!(glob("").matches(""));
+        assert!(!glob("").matches("master"));
+    }
+
+    #[test]
+    fn star_matches_any_run() {
+        assert!(glob("*").matches(""));
+        assert!(glob("*").matches("feature/nested/branch"));
+        assert!(glob("feature/*").matches("feature/"));
+        assert!(glob("feature/*").matches("feature/a/b"));
+        assert!(!glob("feature/*").matches("feature"));
+        assert!(glob("*-docs").matches("new-docs"));
+        assert!(!glob("*-docs").matches("new-docs-2"));
+        assert!(glob("v*.*").matches("v10.1"));
+        assert!(!glob("v*.*").matches("v10"));
+    }
+
+    #[test]
+    fn double_star_is_the_same_as_a_star() {
+        assert!(glob("**").matches(""));
+        assert!(glob("**").matches("a/b/c"));
+        assert!(glob("release/**").matches("release/1.0/hotfix"));
+        assert!(glob("**/hotfix").matches("release/1.0/hotfix"));
+        assert!(!glob("**/hotfix").matches("hotfix"));
+    }
+
+    #[test]
+    fn question_mark_matches_one_byte() {
+        assert!(glob("v?").matches("v9"));
+        assert!(!glob("v?").matches("v"));
+        assert!(!glob("v?").matches("v10"));
+        assert!(glob("v?*").matches("v10"));
+    }
+
+    #[test]
+    fn star_backtracks_to_later_matches() {
+        assert!(glob("*ab").matches("aaab"));
+        assert!(glob("a*b*c").matches("abxbxc"));
+        assert!(!glob("a*b*c").matches("abxbx"));
+    }
+
+    #[test]
+    fn many_stars_do_not_blow_up() {
+        let pattern = format!("{}b", "a*".repeat(30));
+        let branch = "a".repeat(100);
+
+        assert!(!glob(&pattern).matches(&branch));
+        assert!(glob(&pattern).matches(&format!("{}b", branch)));
+    }
+
+    #[test]
+    fn regex_patterns_are_wrapped_in_slashes() {
+        let pattern: BranchPattern = serde_json::from_str(r#""/^v\\d+$/""#).unwrap();
+
+        assert!(pattern.matches("v10"));
+        assert!(!pattern.matches("v10-beta"));
+        assert_eq!(serde_json::to_string(&pattern).unwrap(), r#""/^v\\d+$/""#);
+        assert!(matches!(
+            serde_json::from_str::<BranchPattern>(r#""release/*""#).unwrap(),
+            BranchPattern::Glob(_)
+        ));
+    }
+
+    #[test]
+    fn first_matching_rule_decides() {
+        let route: Route = serde_json::from_str(
+            r#"{
+                "name": "docs",
+                "repo": "discord/discord-api-docs",
+                "branches": [
+                    { "pattern": "master", "action": "relay" },
+                    { "pattern": "*", "action": "relay-read-only" }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(route.action_for("master"), BranchAction::Relay);
+        assert_eq!(route.action_for("other"), BranchAction::RelayReadOnly);
+        assert!(route.is_for("discord-api-docs"));
+        assert!(!route.is_for("discord/other"));
+    }
+}
@@ -0,0 +1,67 @@
+use crate::types::{BotState, State};
+use std::{
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
+
+/// Wait until the process is asked to stop, by SIGTERM or SIGINT (Ctrl+C).
+pub async fn signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(err) => {
+                tracing::warn!("Could not listen for SIGTERM: {}", err);
+                tokio::signal::ctrl_c().await.ok();
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await.ok();
+}
+
+/// Stop starting new work and give running issue creation jobs time to reach their next checkpoint.
+///
+/// Jobs which are not done by then are resumed on the next start.
+pub async fn drain(state: &State, timeout: Duration) {
+    state.shutting_down.store(true, Ordering::SeqCst);
+
+    let drained = tokio::time::timeout(timeout, async {
+        while state.running_jobs.load(Ordering::SeqCst) != 0 {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await;
+
+    if drained.is_err() {
+        tracing::warn!(
+            "{} issue creation job(s) did not stop in time",
+            state.running_jobs.load(Ordering::SeqCst)
+        );
+    }
+}
+
+/// Counts a job as running for as long as it is alive.
+pub struct JobGuard(Arc<BotState>);
+
+impl JobGuard {
+    pub fn new(state: &State) -> Self {
+        state.running_jobs.fetch_add(1, Ordering::SeqCst);
+
+        Self(Arc::clone(state))
+    }
+}
+
+impl Drop for JobGuard {
+    fn drop(&mut self) {
+        self.0.running_jobs.fetch_sub(1, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,431 @@
+use crate::types::github::GitHubCommit;
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    fs, io,
+    path::Path,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum SkipRulesError {
+    #[error("Error while reading the skip rules file: {0}")]
+    Io(#[from] io::Error),
+    #[error("Error while parsing the skip rules file: {0}")]
+    Serde(#[from] serde_json::Error),
+}
+
+/// Rules for upstream commits which need no change in the library, like typo or link fixes.
+///
+/// A commit is skipped as soon as one of the rules matches.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SkipRules {
+    /// Regexes which are matched against the first line of the commit message.
+    #[serde(deserialize_with = "deserialize_regexes")]
+    pub title_patterns: Vec<Regex>,
+    /// Commits which add and remove fewer lines than this in total.
+    pub min_changed_lines: Option<i64>,
+    /// Commits which only change whitespace.
+    pub skip_whitespace_only: bool,
+    /// Commits which do not touch anything inside this path, like `docs/`.
+    pub docs_path: Option<String>,
+}
+
+fn deserialize_regexes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Regex>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|pattern| Regex::new(pattern).map_err(serde::de::Error::custom))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    Title(String),
+    TooSmall(i64),
+    WhitespaceOnly,
+    OutsideDocs,
+}
+
+impl Display for SkipReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Title(pattern) => write!(f, "title matches `{}`", pattern),
+            Self::TooSmall(lines) => write!(f, "only {} changed lines", lines),
+            Self::WhitespaceOnly => f.write_str("only whitespace changes"),
+            Self::OutsideDocs => f.write_str("no documentation changes"),
+        }
+    }
+}
+
+/// A commit no issue was created for.
+#[derive(Debug, Clone)]
+pub struct SkippedCommit {
+    pub commit: GitHubCommit,
+    pub reason: SkipReason,
+}
+
+impl Display for SkippedCommit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let title = self
+            .commit
+            .commit
+            .message
+            .lines()
+            .next()
+            .unwrap_or_default();
+
+        write!(
+            f,
+            "`{}` {} ({})",
+            self.commit.sha.get(..7).unwrap_or(&self.commit.sha),
+            title,
+            self.reason
+        )
+    }
+}
+
+impl SkipRules {
+    fn title_match(&self, commit: &GitHubCommit) -> Option<&Regex> {
+        let title = commit.commit.message.lines().next().unwrap_or_default();
+
+        self.title_patterns
+            .iter()
+            .find(|pattern| pattern.is_match(title))
+    }
+
+    /// Whether checking the commit needs its stats and files, commits a title pattern
+    /// skips never do.
+    pub fn needs_details(&self, commit: &GitHubCommit) -> bool {
+        (self.min_changed_lines.is_some() || self.skip_whitespace_only || self.docs_path.is_some())
+            && self.title_match(commit).is_none()
+    }
+
+    /// Find the first rule which skips the commit.
+    pub fn check(&self, commit: &GitHubCommit) -> Option<SkipReason> {
+        if let Some(pattern) = self.title_match(commit) {
+            return Some(SkipReason::Title(pattern.as_str().into()));
+        }
+
+        if let Some(min) = self.min_changed_lines {
+            if commit.stats.total < min {
+                return Some(SkipReason::TooSmall(commit.stats.total));
+            }
+        }
+
+        if self.skip_whitespace_only && is_whitespace_only(commit) {
+            return Some(SkipReason::WhitespaceOnly);
+        }
+
+        if let Some(docs_path) = &self.docs_path {
+            if !commit
+                .files
+                .iter()
+                .any(|file| file.filename.starts_with(docs_path.as_str()))
+            {
+                return Some(SkipReason::OutsideDocs);
+            }
+        }
+
+        None
+    }
+
+    /// Split the commits into the ones which need an issue and the skipped ones.
+    pub fn partition(&self, commits: Vec<GitHubCommit>) -> (Vec<GitHubCommit>, Vec<SkippedCommit>) {
+        let mut kept = vec![];
+        let mut skipped = vec![];
+
+        for commit in commits {
+            match self.check(&commit) {
+                Some(reason) => skipped.push(SkippedCommit { commit, reason }),
+                None => kept.push(commit),
+            }
+        }
+
+        (kept, skipped)
+    }
+}
+
+/// Whether the removed and added lines are the same once all whitespace is ignored.
+fn is_whitespace_only(commit: &GitHubCommit) -> bool {
+    // Binary or huge files come without a patch, so nothing can be said about them
+    if commit.files.is_empty() || commit.files.iter().any(|file| file.patch.is_empty()) {
+        return false;
+    }
+
+    let mut removed = vec![];
+    let mut added = vec![];
+
+    for line in commit.files.iter().flat_map(|file| file.patch.lines()) {
+        let stripped = |line: &str| {
+            line.chars()
+                .filter(|c| !c.is_whitespace())
+                .collect::<String>()
+        };
+
+        if let Some(line) = line.strip_prefix('-') {
+            removed.push(stripped(line));
+        } else if let Some(line) = line.strip_prefix('+') {
+            added.push(stripped(line));
+        }
+    }
+
+    // Lines which became empty were only whitespace to begin with
+    removed.retain(|line| !line.is_empty());
+    added.retain(|line| !line.is_empty());
+    removed.sort_unstable();
+    added.sort_unstable();
+
+    removed == added
+}
+
+/// Load the skip rules, without a file no commit is skipped.
+pub fn load_skip_rules(path: &Path) -> Result<SkipRules, SkipRulesError> {
+    match fs::read(path) {
+        Ok(raw) => Ok(serde_json::from_slice(&raw)?),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(SkipRules::default()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::github::{Commit, File, Stats};
+
+    fn commit(message: &str, total: i64, files: &[(&str, &str)]) -> GitHubCommit {
+        GitHubCommit {
+            sha: "0123456789abcdef".into(),
+            commit: Commit {
+                message: message.into(),
+                ..Default::default()
+            },
+            stats: Stats {
+                total,
+                ..Default::default()
+            },
+            files: files
+                .iter()
+                .map(|(filename, patch)| File {
+                    filename: (*filename).into(),
+                    patch: (*patch).into(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn rules() -> SkipRules {
+        serde_json::from_str(include_str!("../../skip_rules.example.json")).unwrap()
+    }
+
+    const FIELD_PATCH: &str = "@@ -1,2 +1,3 @@
+ | id | snowflake | the id |
++| name | string | the name |";
+
+    #[test]
+    fn no_rules_skip_nothing() {
+        let rules = SkipRules::default();
+        let typo = commit("Fix typo", 0, &[]);
+
+        assert_eq!(rules.check(&typo), None);
+        assert!(!rules.needs_details(&typo));
+    }
+
+    #[test]
+    fn title_patterns_match_the_first_line() {
+        let rules = rules();
+
+        assert_eq!(
+            rules.check(&commit("Fixed links\n\nIn the guild docs", 1, &[])),
+            Some(SkipReason::Title("(?i)^fix(ed)? (typo|link)s?\\b".into()))
+        );
+        assert_eq!(
+            rules.check(&commit("formatting of tables", 1, &[])),
+            Some(SkipReason::Title("(?i)^formatting\\b".into()))
+        );
+        // Only the title counts, not the body
+        assert_eq!(
+            rules.check(&commit(
+                "Add a field\n\nFix typo",
+                10,
+                &[("docs/Guild.md", FIELD_PATCH)]
+            )),
+            None
+        );
+    }
+
+    #[test]
+    fn small_commits_are_skipped() {
+        let rules = rules();
+
+        assert_eq!(
+            rules.check(&commit("Add a field", 2, &[("docs/Guild.md", FIELD_PATCH)])),
+            Some(SkipReason::TooSmall(2))
+        );
+        assert_eq!(
+            rules.check(&commit("Add a field", 3, &[("docs/Guild.md", FIELD_PATCH)])),
+            None
+        );
+    }
+
+    #[test]
+    fn whitespace_changes_are_skipped() {
+        let rules = rules();
+        let patch = "@@ -1,3 +1,3 @@
+-| id  | snowflake |
++| id | snowflake |
+ | type | integer |";
+
+        assert_eq!(
+            rules.check(&commit("Align the table", 4, &[("docs/Guild.md", patch)])),
+            Some(SkipReason::WhitespaceOnly)
+        );
+    }
+
+    #[test]
+    fn commits_outside_the_docs_are_skipped() {
+        let rules = rules();
+
+        assert_eq!(
+            rules.check(&commit(
+                "Bump deps",
+                10,
+                &[("package.json", "@@ -1 +1 @@\n-a\n+b")]
+            )),
+            Some(SkipReason::OutsideDocs)
+        );
+        assert_eq!(
+            rules.check(&commit(
+                "Bump deps and docs",
+                10,
+                &[
+                    ("package.json", "@@ -1 +1 @@\n-a\n+b"),
+                    ("docs/Guild.md", FIELD_PATCH)
+                ]
+            )),
+            None
+        );
+    }
+
+    #[test]
+    fn the_first_matching_rule_wins() {
+        let rules = rules();
+
+        assert_eq!(
+            rules.check(&commit("Fix typo", 1, &[("package.json", "")])),
+            Some(SkipReason::Title("(?i)^fix(ed)? (typo|link)s?\\b".into()))
+        );
+        assert_eq!(
+            rules.check(&commit(
+                "Bump",
+                1,
+                &[("package.json", "@@ -1 +1 @@\n-a\n+b")]
+            )),
+            Some(SkipReason::TooSmall(1))
+        );
+    }
+
+    #[test]
+    fn details_are_only_needed_for_commits_no_title_skips() {
+        let rules = rules();
+
+        assert!(!rules.needs_details(&commit("Fix typo", 0, &[])));
+        assert!(rules.needs_details(&commit("Add a field", 0, &[])));
+
+        let titles_only = SkipRules {
+            title_patterns: rules.title_patterns.clone(),
+            ..Default::default()
+        };
+        assert!(!titles_only.needs_details(&commit("Add a field", 0, &[])));
+    }
+
+    #[test]
+    fn partition_keeps_the_order() {
+        let rules = rules();
+        let commits = vec![
+            commit("Add a field", 3, &[("docs/Guild.md", FIELD_PATCH)]),
+            commit("Fix typo", 1, &[]),
+            commit("Add another field", 3, &[("docs/User.md", FIELD_PATCH)]),
+        ];
+
+        let (kept, skipped) = rules.partition(commits);
+
+        let titles: Vec<_> = kept
+            .iter()
+            .map(|commit| commit.commit.message.as_str())
+            .collect();
+        assert_eq!(titles, ["Add a field", "Add another field"]);
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(
+            skipped[0].to_string(),
+            "`0123456` Fix typo (title matches `(?i)^fix(ed)? (typo|link)s?\\b`)"
+        );
+    }
+
+    #[test]
+    fn moved_lines_are_whitespace_only() {
+        let patch = "@@ -1,4 +1,4 @@
+-a b
+ c
+-
++
++ab
+\tnot a change";
+
+        assert!(is_whitespace_only(&commit("", 0, &[("docs/A.md", patch)])));
+    }
+
+    #[test]
+    fn changed_words_are_not_whitespace_only() {
+        let patch = "@@ -1 +1 @@
+-| id | snowflake |
++| id | integer |";
+
+        assert!(!is_whitespace_only(&commit("", 0, &[("docs/A.md", patch)])));
+        // Only added or only removed lines are a change as well
+        assert!(!is_whitespace_only(&commit(
+            "",
+            0,
+            &[("docs/A.md", "@@ -1 +1,2 @@\n+new")]
+        )));
+        assert!(!is_whitespace_only(&commit(
+            "",
+            0,
+            &[("docs/A.md", "@@ -1,2 +1 @@\n-old")]
+        )));
+    }
+
+    #[test]
+    fn lines_can_move_between_files() {
+        let commit = commit(
+            "",
+            0,
+            &[
+                ("docs/A.md", "@@ -1 +0,0 @@\n-| id | snowflake |"),
+                ("docs/B.md", "@@ -0,0 +1 @@\n+|id|snowflake|"),
+            ],
+        );
+
+        assert!(is_whitespace_only(&commit));
+    }
+
+    #[test]
+    fn files_without_patches_are_not_whitespace_only() {
+        assert!(!is_whitespace_only(&commit("", 0, &[])));
+        assert!(!is_whitespace_only(&commit(
+            "",
+            0,
+            &[("docs/image.png", "")]
+        )));
+        assert!(!is_whitespace_only(&commit(
+            "",
+            0,
+            &[
+                ("docs/A.md", "@@ -1 +1 @@\n-a \n+a"),
+                ("docs/image.png", "")
+            ]
+        )));
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{
+    metrics::Recorded,
+    types::{
+        github::{GitHubCommit, GitHubIssue},
+        State,
+    },
+};
+use std::error::Error;
+use twilight_model::{
+    channel::Message,
+    id::{marker::ChannelMarker, Id},
+};
+
+/// The longest name Discord allows for a thread.
+const MAX_THREAD_NAME_LEN: usize = 100;
+
+type ThreadResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Get the thread of a relayed message, opening it with the given name if there is none yet.
+pub async fn thread_for(
+    state: &State,
+    message_id: u64,
+    name: &str,
+) -> ThreadResult<Id<ChannelMarker>> {
+    if let Some(thread_id) = state
+        .database
+        .read(|data| data.threads.get(&message_id).copied())
+    {
+        return Ok(Id::new(thread_id));
+    }
+
+    let name: String = name.chars().take(MAX_THREAD_NAME_LEN).collect();
+    let channel_id = state.database.read(|data| data.channel_of(message_id));
+    let thread = state
+        .http
+        .create_thread_from_message(channel_id, Id::new(message_id), &name)?
+        .exec()
+        .recorded()
+        .await?
+        .model()
+        .await?;
+
+    state.database.write(|data| {
+        data.threads.insert(message_id, thread.id.get());
+    })?;
+
+    Ok(thread.id)
+}
+
+/// Open a thread for the discussion of a relayed push, named after its commit.
+///
+/// Compares get the details of every commit posted into the thread.
+pub async fn open_push_thread(
+    state: &State,
+    message: &Message,
+    commits: &[GitHubCommit],
+) -> ThreadResult<()> {
+    let title = |message: &str| message.lines().next().unwrap_or_default().to_owned();
+
+    let name = match commits {
+        [] => return Ok(()),
+        [commit] => title(&commit.commit.message),
+        [.., last] => format!(
+            "{} (+{} more)",
+            title(&last.commit.message),
+            commits.len() - 1
+        ),
+    };
+
+    let thread_id = thread_for(state, message.id.get(), &name).await?;
+
+    if commits.len() > 1 {
+        for commit in commits {
+            let details = format!(
+                "**{}**\n`{}` by {} (+{} -{})\n<{}>",
+                title(&commit.commit.message),
+                commit.sha.get(..7).unwrap_or(&commit.sha),
+                commit.commit.author.name,
+                commit.stats.additions,
+                commit.stats.deletions,
+                commit.html_url
+            );
+
+            state
+                .http
+                .create_message(thread_id)
+                .content(&details)?
+                .exec()
+                .recorded()
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Post the links of created issues into the thread of the relayed message, if it has one.
+pub async fn mirror_issues(
+    state: &State,
+    message_id: u64,
+    issues: &[GitHubIssue],
+) -> ThreadResult<()> {
+    let thread_id = match state
+        .database
+        .read(|data| data.threads.get(&message_id).copied())
+    {
+        Some(thread_id) if !issues.is_empty() => Id::new(thread_id),
+        _ => return Ok(()),
+    };
+
+    let content = issues
+        .iter()
+        .map(|issue| {
+            format!(
+                "Created [#{}](<{}>): {}",
+                issue.number, issue.html_url, issue.title
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    // Stay within the message content limit
+    let content: String = content.chars().take(2000).collect();
+
+    state
+        .http
+        .create_message(thread_id)
+        .content(&content)?
+        .exec()
+        .recorded()
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,372 @@
+use crate::{
+    constants::{
+        ISSUE_ASSIGNED_COLOR, ISSUE_CLOSED_COLOR, ISSUE_IMPLEMENTED_COLOR, ISSUE_OPEN_COLOR,
+    },
+    database::{CreatedIssue, HistoryEvent, IssueStatus},
+    github::{fetch_merged_pull_request, fetch_updated_issues},
+    metrics::Recorded,
+    threads::thread_for,
+    types::{github::GitHubIssue, State},
+};
+use chrono::{SecondsFormat, TimeZone, Utc};
+use std::{collections::HashMap, error::Error};
+use twilight_model::{channel::embed::EmbedField, id::Id};
+
+/// Discord rejects embeds with more fields than this.
+const MAX_EMBED_FIELDS: usize = 25;
+/// Discord rejects messages with more characters than this.
+const MAX_MESSAGE_LEN: usize = 2000;
+
+type TrackerResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Keep the relayed messages in sync with the state of the issues created from them.
+pub async fn track_issues(state: State) {
+    let mut interval = tokio::time::interval(state.config.issue_sync_interval);
+
+    loop {
+        interval.tick().await;
+
+        if let Err(err) = sync_issues(&state).await {
+            tracing::warn!("Could not sync the state of the created issues: {}", err);
+        }
+    }
+}
+
+/// The open issues of one relayed message.
+struct TrackedMessage {
+    message_id: u64,
+    created_at: i64,
+    issues: Vec<CreatedIssue>,
+}
+
+async fn sync_issues(state: &State) -> TrackerResult<()> {
+    // Closed issues are not followed anymore
+    let tracked: Vec<(String, TrackedMessage)> = state.database.read(|data| {
+        data.created_issues
+            .iter()
+            .filter(|(_, created)| created.issues.iter().any(|issue| !issue.closed))
+            .map(|(message_id, created)| {
+                (
+                    created.repo.clone(),
+                    TrackedMessage {
+                        message_id: *message_id,
+                        created_at: created.created_at,
+                        issues: created.issues.clone(),
+                    },
+                )
+            })
+            .collect()
+    });
+
+    let mut repos: HashMap<String, Vec<TrackedMessage>> = HashMap::new();
+    for (repo, message) in tracked {
+        repos.entry(repo).or_default().push(message);
+    }
+
+    for (repo, messages) in repos {
+        if let Err(err) = sync_repo(state, &repo, messages).await {
+            tracing::warn!("Could not sync the issues of {}: {}", repo, err);
+        }
+    }
+
+    Ok(())
+}
+
+/// Sync the tracked issues of a repo which changed since the last sync.
+async fn sync_repo(state: &State, repo: &str, messages: Vec<TrackedMessage>) -> TrackerResult<()> {
+    let started_at = Utc::now().timestamp();
+    // The first sync of a repo goes back to its oldest tracked issue
+    let since = state
+        .database
+        .read(|data| data.issues_synced_at.get(repo).copied())
+        .or_else(|| messages.iter().map(|message| message.created_at).min())
+        .unwrap_or(started_at);
+    let since = Utc
+        .timestamp_opt(since, 0)
+        .single()
+        .unwrap_or_else(Utc::now)
+        .to_rfc3339_opts(SecondsFormat::Secs, true);
+
+    let updated: HashMap<i64, GitHubIssue> = fetch_updated_issues(&state.github, repo, &since)
+        .await?
+        .into_iter()
+        .map(|issue| (issue.number, issue))
+        .collect();
+
+    // Messages which were synced are not changed again, so the issues of a message which
+    // failed are fetched again next time by not moving the sync forward
+    let mut failed = false;
+    for message in messages {
+        let message_id = message.message_id;
+        if let Err(err) = sync_message(state, repo, message, &updated).await {
+            tracing::warn!("Could not sync the issues of {}: {}", message_id, err);
+            failed = true;
+        }
+    }
+
+    if !failed {
+        state
+            .database
+            .write(|data| data.issues_synced_at.insert(repo.to_owned(), started_at))?;
+    }
+
+    Ok(())
+}
+
+async fn sync_message(
+    state: &State,
+    repo: &str,
+    message: TrackedMessage,
+    updated: &HashMap<i64, GitHubIssue>,
+) -> TrackerResult<()> {
+    let TrackedMessage {
+        message_id,
+        mut issues,
+        ..
+    } = message;
+    let mut changes = vec![];
+    let mut relabeled = false;
+
+    for issue in issues.iter_mut().filter(|issue| !issue.closed) {
+        let remote = match updated.get(&issue.number) {
+            Some(remote) => remote,
+            None => continue,
+        };
+        let assignees: Vec<_> = remote
+            .assignees
+            .iter()
+            .map(|assignee| assignee.login.clone())
+            .collect();
+        let closed = remote.state == "closed";
+
+        let pull_request = if closed {
+            fetch_merged_pull_request(&state.github, repo, issue.number).await?
+        } else {
+            None
+        };
+
+        let status = match (closed, &pull_request) {
+            (true, Some(_)) => IssueStatus::Implemented,
+            (true, None) => IssueStatus::Closed,
+            (false, _) if !assignees.is_empty() => IssueStatus::Assigned,
+            (false, _) => IssueStatus::Open,
+        };
+
+        let labels: Vec<_> = remote
+            .labels
+            .iter()
+            .map(|label| label.name.clone())
+            .collect();
+        // Labels are only kept for the digest, changing them is not worth a message
+        if labels != issue.labels {
+            issue.labels = labels;
+            relabeled = true;
+        }
+
+        if status == issue.status && assignees == issue.assignees {
+            continue;
+        }
+
+        issue.closed = closed;
+        issue.status = status;
+        issue.assignees = assignees;
+        issue.pull_request = pull_request;
+
+        changes.push(issue.clone());
+    }
+
+    if changes.is_empty() && !relabeled {
+        return Ok(());
+    }
+
+    // The changes are only saved once they were shown, so they are not lost when Discord fails
+    if !changes.is_empty() {
+        update_relay_message(state, message_id, &issues).await?;
+        post_changes(state, message_id, &changes).await?;
+    }
+
+    state.database.write(|data| {
+        if let Some(created) = data.created_issues.get_mut(&message_id) {
+            for recorded in created.issues.iter_mut() {
+                if let Some(synced) = issues.iter().find(|issue| issue.number == recorded.number) {
+                    *recorded = synced.clone();
+                }
+            }
+        }
+
+        for issue in changes.iter().filter(|issue| issue.closed) {
+            data.log(HistoryEvent::IssueClosed {
+                repo: repo.to_owned(),
+                number: issue.number,
+            });
+        }
+    })?;
+
+    Ok(())
+}
+
+fn status_text(issue: &CreatedIssue) -> String {
+    match issue.status {
+        IssueStatus::Open => "Open".into(),
+        IssueStatus::Assigned => format!("Assigned to {}", issue.assignees.join(", ")),
+        IssueStatus::Closed => "Closed".into(),
+        IssueStatus::Implemented => match &issue.pull_request {
+            Some(url) => format!("Implemented in {}", url),
+            None => "Implemented".into(),
+        },
+    }
+}
+
+fn status_color(issues: &[CreatedIssue]) -> u32 {
+    if issues
+        .iter()
+        .all(|issue| issue.status == IssueStatus::Implemented)
+    {
+        ISSUE_IMPLEMENTED_COLOR
+    } else if issues.iter().all(|issue| issue.closed) {
+        ISSUE_CLOSED_COLOR
+    } else if issues
+        .iter()
+        .any(|issue| issue.status == IssueStatus::Assigned)
+    {
+        ISSUE_ASSIGNED_COLOR
+    } else {
+        ISSUE_OPEN_COLOR
+    }
+}
+
+/// Show the state of every issue in the embed of the relayed message.
+async fn update_relay_message(
+    state: &State,
+    message_id: u64,
+    issues: &[CreatedIssue],
+) -> TrackerResult<()> {
+    let channel_id = state.database.read(|data| data.channel_of(message_id));
+    let message = state
+        .http
+        .message(channel_id, Id::new(message_id))
+        .exec()
+        .recorded()
+        .await?
+        .model()
+        .await?;
+
+    let mut embeds = message.embeds;
+    if let Some(embed) = embeds.first_mut() {
+        embed.color = Some(status_color(issues));
+        embed
+            .fields
+            .retain(|field| !field.name.starts_with("Issue #"));
+        // Embeds have at most 25 fields, large compares only show their first issues
+        let room = MAX_EMBED_FIELDS.saturating_sub(embed.fields.len());
+        embed
+            .fields
+            .extend(issues.iter().take(room).map(|issue| EmbedField {
+                inline: true,
+                name: format!("Issue #{}", issue.number),
+                value: status_text(issue),
+            }));
+    }
+
+    state
+        .http
+        .update_message(channel_id, message.id)
+        .embeds(Some(&embeds))?
+        .exec()
+        .recorded()
+        .await?;
+
+    Ok(())
+}
+
+/// Post the changes into the thread of the relayed message, opening it if needed.
+async fn post_changes(
+    state: &State,
+    message_id: u64,
+    changes: &[CreatedIssue],
+) -> TrackerResult<()> {
+    let thread_id = thread_for(state, message_id, "Issue tracking").await?;
+
+    let lines = changes.iter().map(|issue| {
+        format!(
+            "[#{}](<{}>): {}",
+            issue.number,
+            issue.html_url,
+            status_text(issue)
+        )
+    });
+
+    for content in chunk_lines(lines) {
+        state
+            .http
+            .create_message(thread_id)
+            .content(&content)?
+            .exec()
+            .recorded()
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Put the lines into as few messages as possible, lines which are too long on their own are cut.
+fn chunk_lines(lines: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut chunks: Vec<String> = vec![];
+    // The characters of the last chunk, `len` would count bytes
+    let mut last_len = 0;
+
+    for line in lines {
+        let line: String = line.chars().take(MAX_MESSAGE_LEN).collect();
+        let line_len = line.chars().count();
+
+        match chunks.last_mut() {
+            Some(chunk) if last_len + 1 + line_len <= MAX_MESSAGE_LEN => {
+                chunk.push('\n');
+                chunk.push_str(&line);
+                last_len += 1 + line_len;
+            }
+            _ => {
+                chunks.push(line);
+                last_len = line_len;
+            }
+        }
+    }
+
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_lines_share_a_message() {
+        let lines = ["a".to_owned(), "b".to_owned(), "c".to_owned()];
+
+        assert_eq!(chunk_lines(lines), ["a\nb\nc"]);
+        assert!(chunk_lines(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn messages_stay_within_the_limit() {
+        let lines = vec!["é".repeat(999); 5];
+        let chunks = chunk_lines(lines);
+
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(
+            chunks[0],
+            format!("{}\n{}", "é".repeat(999), "é".repeat(999))
+        );
+        assert_eq!(chunks[2], "é".repeat(999));
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk.chars().count() <= MAX_MESSAGE_LEN));
+    }
+
+    #[test]
+    fn long_lines_are_cut() {
+        let chunks = chunk_lines(["x".repeat(2500), "y".to_owned()]);
+
+        assert_eq!(chunks, ["x".repeat(2000), "y".to_owned()]);
+    }
+}
@@ -0,0 +1,264 @@
+use crate::classify::{ChangeSummary, FieldChange};
+use regex::Regex;
+use std::collections::BTreeMap;
+
+/// The lines of a suggested interface diff, keyed by field so the diff stays sorted.
+type InterfaceLines = BTreeMap<String, Vec<String>>;
+
+/// Suggest how the Discordeno types change, based on the object structure tables of a commit.
+///
+/// Only tables under a `... Structure` heading are used, other tables (params, flags) are no types.
+pub fn suggest_type_diff(changes: &ChangeSummary) -> Option<String> {
+    let link = Regex::new(r"\[([^\]]*)\]\([^)]*\)").unwrap();
+    let mut interfaces: BTreeMap<String, InterfaceLines> = BTreeMap::new();
+
+    let structures = |changes: &[FieldChange]| -> Vec<FieldChange> {
+        changes
+            .iter()
+            .filter(|change| change.table.ends_with(" Structure"))
+            .cloned()
+            .collect()
+    };
+
+    for change in structures(&changes.new_fields) {
+        interfaces
+            .entry(interface_name(&change.table))
+            .or_default()
+            .entry(change.field.clone())
+            .or_default()
+            .extend(added_lines(&link, &change, &change.kind));
+    }
+
+    for change in structures(&changes.removed_fields) {
+        interfaces
+            .entry(interface_name(&change.table))
+            .or_default()
+            .entry(change.field.clone())
+            .or_default()
+            .push(format!(
+                "-  {}",
+                field_line(&link, &change.field, &change.kind)
+            ));
+    }
+
+    for change in structures(&changes.changed_types) {
+        let old_kind = change.old_kind.as_deref().unwrap_or_default();
+        let lines = interfaces
+            .entry(interface_name(&change.table))
+            .or_default()
+            .entry(change.field.clone())
+            .or_default();
+
+        lines.push(format!("-  {}", field_line(&link, &change.field, old_kind)));
+        lines.extend(added_lines(&link, &change, &change.kind));
+    }
+
+    if interfaces.is_empty() {
+        return None;
+    }
+
+    let blocks = interfaces
+        .into_iter()
+        .map(|(name, fields)| {
+            let mut lines = vec![format!(" export interface {} {{", name)];
+            lines.extend(fields.into_values().flatten());
+            lines.push(" }".into());
+
+            lines.join("\n")
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    Some(format!("```diff\n{}\n```", blocks))
+}
+
+/// `Guild Member Structure` becomes `DiscordGuildMember`.
+fn interface_name(table: &str) -> String {
+    let name = table.trim_end_matches(" Structure");
+
+    format!("Discord{}", pascal_case(name))
+}
+
+fn pascal_case(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+fn added_lines(link: &Regex, change: &FieldChange, kind: &str) -> Vec<String> {
+    let mut lines = vec![];
+
+    let description = link.replace_all(&change.description, "$1");
+    if !description.is_empty() {
+        lines.push(format!("+  /** {} */", description));
+    }
+    lines.push(format!("+  {}", field_line(link, &change.field, kind)));
+
+    lines
+}
+
+/// Render a field, `guild_id?` with `?snowflake` becomes `guild_id?: string | null;`.
+fn field_line(link: &Regex, field: &str, kind: &str) -> String {
+    format!("{}: {};", field, typescript_type(link, kind))
+}
+
+/// Translate a documented type into a TypeScript type.
+fn typescript_type(link: &Regex, kind: &str) -> String {
+    let (nullable, kind) = match kind.strip_prefix('?') {
+        Some(kind) => (true, kind),
+        None => (false, kind),
+    };
+    let kind = link.replace_all(kind, "$1").trim().to_lowercase();
+
+    let ts = match kind
+        .strip_prefix("array of ")
+        .or_else(|| kind.strip_prefix("list of "))
+    {
+        Some(element) => format!("{}[]", element_type(element)),
+        None => element_type(&kind),
+    };
+
+    if nullable {
+        format!("{} | null", ts)
+    } else {
+        ts
+    }
+}
+
+fn element_type(kind: &str) -> String {
+    let kind = kind.trim();
+
+    // Like `array of role object ids`
+    if kind.ends_with(" id") || kind.ends_with(" ids") {
+        return "string".into();
+    }
+
+    if let Some(object) = kind
+        .strip_suffix(" objects")
+        .or_else(|| kind.strip_suffix(" object"))
+    {
+        return format!("Discord{}", pascal_case(object));
+    }
+
+    match kind.trim_end_matches('s') {
+        "string" | "snowflake" | "iso8601 timestamp" | "file content" => "string".into(),
+        "integer" | "double" | "float" | "number" => "number".into(),
+        "boolean" => "boolean".into(),
+        "dict" => "Record<string, unknown>".into(),
+        _ => format!("unknown /* {} */", kind),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::github::File;
+
+    fn suggest(patch: &str) -> Option<String> {
+        suggest_type_diff(&ChangeSummary::analyze(&[File {
+            filename: "docs/resources/Channel.md".into(),
+            patch: patch.into(),
+            ..Default::default()
+        }]))
+    }
+
+    #[test]
+    fn new_field_with_its_description() {
+        let diff = suggest(
+            "@@ -30,6 +30,7 @@ Represents a guild or DM channel within Discord.
+ ###### Channel Structure
+ 
+ | id | snowflake | the id of this channel |
++| default_forum_layout? | integer | the [default forum layout view](#DOCS_RESOURCES_CHANNEL/forum-layout-types) used to display posts in `GUILD_FORUM` channels |",
+        );
+
+        assert_eq!(
+            diff.unwrap(),
+            "```diff
+ export interface DiscordChannel {
++  /** the default forum layout view used to display posts in `GUILD_FORUM` channels */
++  default_forum_layout?: number;
+ }
+```"
+        );
+    }
+
+    #[test]
+    fn removed_and_changed_fields() {
+        let diff = suggest(
+            "@@ -60,9 +60,8 @@ Guilds in Discord represent an isolated collection of users and channels.
+ ###### Guild Structure
+ 
+-| permissions? \\* | integer | total permissions for [the user](#DOCS_RESOURCES_USER/get-current-user-guilds) in the guild |
++| permissions? \\* | string | total permissions for [the user](#DOCS_RESOURCES_USER/get-current-user-guilds) in the guild |
+-| max_presences? | ?integer | the maximum number of presences for the guild |
+ | max_members? | integer | the maximum number of members for the guild |",
+        );
+
+        assert_eq!(
+            diff.unwrap(),
+            "```diff
+ export interface DiscordGuild {
+-  max_presences?: number | null;
+-  permissions?: number;
++  /** total permissions for the user in the guild */
++  permissions?: string;
+ }
+```"
+        );
+    }
+
+    #[test]
+    fn arrays_objects_and_nullable_types() {
+        let diff = suggest(
+            "@@ -220,6 +220,9 @@ The thread metadata object contains a number of thread-specific channel fields
+ ###### Thread Metadata Structure
+ 
++| create_timestamp? | ?ISO8601 timestamp | |
++| applied_tags? | array of snowflakes | |
++| available_tags? | array of [tag](#DOCS_RESOURCES_CHANNEL/forum-tag-object) objects | |
++| role_ids | array of [role](#DOCS_TOPICS_PERMISSIONS/role-object) object ids | |",
+        )
+        .unwrap();
+
+        assert!(diff.contains(" export interface DiscordThreadMetadata {"));
+        assert!(diff.contains("+  create_timestamp?: string | null;"));
+        assert!(diff.contains("+  applied_tags?: string[];"));
+        assert!(diff.contains("+  available_tags?: DiscordTag[];"));
+        assert!(diff.contains("+  role_ids: string[];"));
+        // Empty descriptions get no doc comment
+        assert!(!diff.contains("/**"));
+    }
+
+    #[test]
+    fn only_structure_tables_are_types() {
+        let diff = suggest(
+            "@@ -300,6 +300,7 @@ Returns the guild object for the given id.
+ ###### Query String Params
+ 
+ | Field | Type | Description | Default |
++| with_counts? | [boolean](#DOCS_REFERENCE/boolean-query-strings) | when `true`, will return approximate member and presence counts for the guild | false |",
+        );
+
+        assert_eq!(diff, None);
+    }
+
+    #[test]
+    fn interface_names_are_pascal_case() {
+        assert_eq!(
+            interface_name("Guild Member Structure"),
+            "DiscordGuildMember"
+        );
+        assert_eq!(
+            interface_name("Auto Moderation Rule Structure"),
+            "DiscordAutoModerationRule"
+        );
+    }
+}
@@ -125,6 +125,8 @@ pub struct File {
     pub blob_url: String,
     pub raw_url: String,
     pub contents_url: String,
+    /// Missing for binary and very large files.
+    #[serde(default)]
     pub patch: String,
 }
 
@@ -157,3 +159,123 @@ pub struct BaseCommit {
     pub committer: Committer,
     pub parents: Vec<Parent>,
 }
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GitHubIssue {
+    pub id: i64,
+    pub node_id: String,
+    pub url: String,
+    pub html_url: String,
+    pub number: i64,
+    pub state: String,
+    pub title: String,
+    pub body: Option<String>,
+    pub locked: bool,
+    pub labels: Vec<Label>,
+    pub assignees: Vec<Author>,
+    pub closed_at: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Label {
+    pub id: i64,
+    pub node_id: String,
+    pub url: String,
+    pub name: String,
+    pub color: String,
+    pub default: bool,
+    pub description: Option<String>,
+}
+
+/// An entry of the issue timeline, only the parts needed to find linked pull requests are modeled.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimelineEvent {
+    #[serde(default)]
+    pub event: String,
+    #[serde(default)]
+    pub source: Option<TimelineSource>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimelineSource {
+    #[serde(default)]
+    pub issue: Option<TimelineIssue>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimelineIssue {
+    pub number: i64,
+    pub html_url: String,
+    #[serde(default)]
+    pub pull_request: Option<PullRequestRef>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PullRequestRef {
+    pub url: String,
+    pub html_url: String,
+    #[serde(default)]
+    pub merged_at: Option<String>,
+}
+
+/// The payload GitHub sends to webhooks for `push` events.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GitHubPush {
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    pub before: String,
+    pub after: String,
+    pub created: bool,
+    pub deleted: bool,
+    pub forced: bool,
+    pub compare: String,
+    pub commits: Vec<PushCommit>,
+    pub head_commit: Option<PushCommit>,
+    pub repository: PushRepository,
+    pub sender: Author,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PushCommit {
+    pub id: String,
+    pub tree_id: String,
+    pub distinct: bool,
+    pub message: String,
+    pub timestamp: String,
+    pub url: String,
+    pub author: PushCommitAuthor,
+    pub committer: PushCommitAuthor,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PushCommitAuthor {
+    pub name: String,
+    pub email: String,
+    pub username: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PushRepository {
+    pub id: i64,
+    pub node_id: String,
+    pub name: String,
+    pub full_name: String,
+    pub html_url: String,
+    pub default_branch: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GitHubBranch {
+    pub name: String,
+    pub commit: BranchCommit,
+    pub protected: bool,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BranchCommit {
+    pub sha: String,
+    pub url: String,
+}
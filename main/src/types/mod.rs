@@ -1,6 +1,58 @@
 pub mod github;
 
-use std::sync::Arc;
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+use twilight_gateway::cluster::Cluster;
 use twilight_http::Client as HttpClient;
 
+use crate::{config::Config, database::Database, github_auth::GitHubAuth};
+
 pub type TwHttpClient = Arc<HttpClient>;
+
+/// Everything the event handlers need access to.
+pub struct BotState {
+    pub http: TwHttpClient,
+    /// `None` when running without the gateway, like in the CLI.
+    pub cluster: Option<Arc<Cluster>>,
+    pub config: Config,
+    pub database: Database,
+    /// How requests to GitHub are authenticated.
+    pub github: Arc<GitHubAuth>,
+    /// Set once a shutdown was requested, no new work should be started after that.
+    pub shutting_down: AtomicBool,
+    /// How many issue creation jobs are running right now.
+    pub running_jobs: AtomicUsize,
+    pub started_at: Instant,
+}
+
+impl BotState {
+    pub fn new(
+        http: TwHttpClient,
+        cluster: Option<Arc<Cluster>>,
+        config: Config,
+        database: Database,
+        github: Arc<GitHubAuth>,
+    ) -> Self {
+        Self {
+            http,
+            cluster,
+            config,
+            database,
+            github,
+            shutting_down: AtomicBool::new(false),
+            running_jobs: AtomicUsize::new(0),
+            started_at: Instant::now(),
+        }
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+}
+
+pub type State = Arc<BotState>;
@@ -0,0 +1,96 @@
+use crate::{
+    config::WatchedBranch,
+    embeds::compare_embed,
+    github::{fetch_branch_head, fetch_compare, CreateIssuesError},
+    handlers::relay_everywhere,
+    types::State,
+};
+use reqwest::StatusCode;
+use std::{collections::HashMap, error::Error, time::Duration};
+
+/// Poll the watched branches for new commits and relay them, so the bot keeps
+/// working without any webhook.
+pub async fn watch_upstream(state: State, poll_interval: Duration) {
+    let mut interval = tokio::time::interval(poll_interval);
+    // In dry-run mode the heads are only remembered here, so the watch picks up where
+    // the database left off once the dry run is over
+    let mut dry_run_seen = HashMap::new();
+
+    loop {
+        interval.tick().await;
+
+        for watched in &state.config.watched_branches {
+            if let Err(err) = check_branch(&state, watched, &mut dry_run_seen).await {
+                tracing::warn!("Could not check {} for new commits: {}", watched, err);
+            }
+        }
+    }
+}
+
+async fn check_branch(
+    state: &State,
+    watched: &WatchedBranch,
+    dry_run_seen: &mut HashMap<String, String>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let key = watched.to_string();
+    let head = fetch_branch_head(&state.github, &watched.repo, &watched.branch).await?;
+
+    let last_seen = match dry_run_seen.get(&key).cloned().or_else(|| {
+        state
+            .database
+            .read(|data| data.last_seen.get(&key).cloned())
+    }) {
+        Some(last_seen) if last_seen == head => return Ok(()),
+        Some(last_seen) => last_seen,
+        None => {
+            // There is nothing to compare against yet, so start watching from here
+            return remember_head(state, dry_run_seen, key, head);
+        }
+    };
+
+    let compare = match fetch_compare(&state.github, &watched.repo, &last_seen, &head).await {
+        Ok(compare) => compare,
+        // A force push can leave the last seen commit behind, it is gone or unrelated to the head
+        Err(CreateIssuesError::ReqwestError(err))
+            if matches!(
+                err.status(),
+                Some(StatusCode::NOT_FOUND | StatusCode::UNPROCESSABLE_ENTITY)
+            ) =>
+        {
+            tracing::warn!(
+                "Can not compare {} from {} to {}, watching it from the new head on: {}",
+                watched,
+                last_seen,
+                head,
+                err
+            );
+
+            return remember_head(state, dry_run_seen, key, head);
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    if let Some(embed) = compare_embed(&watched.repo, &watched.branch, &compare)? {
+        relay_everywhere(state, &watched.repo, &watched.branch, &[embed]).await;
+    }
+
+    remember_head(state, dry_run_seen, key, head)
+}
+
+/// Remember the head of a branch as relayed.
+fn remember_head(
+    state: &State,
+    dry_run_seen: &mut HashMap<String, String>,
+    key: String,
+    head: String,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if state.config.dry_run {
+        dry_run_seen.insert(key, head);
+    } else {
+        state
+            .database
+            .write(|data| data.last_seen.insert(key, head))?;
+    }
+
+    Ok(())
+}
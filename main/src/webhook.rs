@@ -0,0 +1,189 @@
+use crate::{
+    embeds::{push_embed, RelayCommit},
+    handlers::relay_everywhere,
+    types::{github::GitHubPush, State},
+};
+use axum::{
+    body::Bytes,
+    extract::Extension,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Router,
+};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::{error::Error, net::SocketAddr};
+
+/// Receive GitHub webhooks directly instead of going through the Discord webhook integration.
+///
+/// Only use one of them for a repo, with both set up every push is relayed twice.
+pub async fn serve(state: State, address: SocketAddr) {
+    let app = Router::new()
+        .route("/github", post(receive))
+        .layer(Extension(state));
+
+    tracing::info!("Listening for GitHub webhooks on {}", address);
+
+    if let Err(err) = axum::Server::bind(&address)
+        .serve(app.into_make_service())
+        .await
+    {
+        tracing::error!("The GitHub webhook receiver stopped: {}", err);
+    }
+}
+
+async fn receive(
+    Extension(state): Extension<State>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    let secret = match &state.config.webhook_secret {
+        Some(secret) => secret,
+        None => return StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    let signature = headers
+        .get("X-Hub-Signature-256")
+        .and_then(|value| value.to_str().ok());
+    if !verify_signature(secret, signature, &body) {
+        return StatusCode::UNAUTHORIZED;
+    }
+
+    match headers
+        .get("X-GitHub-Event")
+        .and_then(|value| value.to_str().ok())
+    {
+        Some("push") => {}
+        // Sent once when the webhook is set up
+        Some("ping") => return StatusCode::OK,
+        _ => return StatusCode::ACCEPTED,
+    }
+
+    let push = match serde_json::from_slice::<GitHubPush>(&body) {
+        Ok(push) => push,
+        Err(err) => {
+            tracing::warn!("Could not parse a push webhook: {}", err);
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+
+    // GitHub only waits 10 seconds for a response so relay in the background
+    tokio::spawn(async move {
+        if let Err(err) = relay_push(&state, push).await {
+            tracing::warn!("Could not relay a push webhook: {}", err);
+        }
+    });
+
+    StatusCode::ACCEPTED
+}
+
+/// Check the `sha256=<hex>` signature GitHub sends along with every delivery.
+fn verify_signature(secret: &str, signature: Option<&str>, body: &[u8]) -> bool {
+    let signature = match signature
+        .and_then(|signature| signature.strip_prefix("sha256="))
+        .and_then(|signature| hex::decode(signature).ok())
+    {
+        Some(signature) => signature,
+        None => return false,
+    };
+
+    let mut mac = match Hmac::<Sha256>::new_from_slice(secret.as_bytes()) {
+        Ok(mac) => mac,
+        Err(_) => return false,
+    };
+    mac.update(body);
+
+    mac.verify_slice(&signature).is_ok()
+}
+
+async fn relay_push(state: &State, push: GitHubPush) -> Result<(), Box<dyn Error + Send + Sync>> {
+    // Tags and deleted branches have nothing to relay
+    let branch = match push.git_ref.strip_prefix("refs/heads/") {
+        Some(branch) if !push.deleted && !push.commits.is_empty() => branch,
+        _ => return Ok(()),
+    };
+
+    // A single commit links to the commit, multiple to the compare. The issue flows work with both.
+    let url = if push.commits.len() == 1 {
+        push.commits[0].url.clone()
+    } else {
+        push.compare.clone()
+    };
+
+    let commits: Vec<_> = push
+        .commits
+        .iter()
+        .map(|commit| RelayCommit {
+            sha: &commit.id,
+            url: &commit.url,
+            message: &commit.message,
+            author: &commit.author.name,
+        })
+        .collect();
+
+    let embed = push_embed(
+        &push.repository.name,
+        branch,
+        url,
+        &commits,
+        Some(&push.sender),
+    )?;
+
+    relay_everywhere(state, &push.repository.full_name, branch, &[embed]).await;
+
+    // Let the upstream watcher know these commits were handled already
+    state.database.write(|data| {
+        data.last_seen.insert(
+            format!("{}:{}", push.repository.full_name, branch),
+            push.after.clone(),
+        )
+    })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The example of the GitHub docs on validating webhook deliveries
+    const SECRET: &str = "It's a Secret to Everybody";
+    const BODY: &[u8] = b"Hello, World!";
+    const SIGNATURE: &str =
+        "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+
+    #[test]
+    fn accepts_a_valid_signature() {
+        assert!(verify_signature(SECRET, Some(SIGNATURE), BODY));
+    }
+
+    #[test]
+    fn rejects_a_tampered_body() {
+        assert!(!verify_signature(SECRET, Some(SIGNATURE), b"Hello, World?"));
+    }
+
+    #[test]
+    fn rejects_a_tampered_signature() {
+        let tampered = SIGNATURE.replace("757107", "757108");
+
+        assert!(!verify_signature(SECRET, Some(&tampered), BODY));
+    }
+
+    #[test]
+    fn rejects_another_secret() {
+        assert!(!verify_signature("another secret", Some(SIGNATURE), BODY));
+    }
+
+    #[test]
+    fn rejects_missing_and_malformed_signatures() {
+        assert!(!verify_signature(SECRET, None, BODY));
+        // The old SHA-1 header is not accepted
+        assert!(!verify_signature(
+            SECRET,
+            Some("sha1=01dc10d0c83e72ed246219cdd91669667fe2ca59"),
+            BODY
+        ));
+        assert!(!verify_signature(SECRET, Some("sha256=not-hex"), BODY));
+        assert!(!verify_signature(SECRET, Some(&SIGNATURE[7..]), BODY));
+    }
+}
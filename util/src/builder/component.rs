@@ -1,9 +1,15 @@
-use twilight_model::application::component::{ActionRow, Button, Component, SelectMenu};
+use twilight_model::application::component::{ActionRow, Button, Component, SelectMenu, TextInput};
 
 #[derive(Clone, Debug)]
 #[must_use = "builders have no effect if unused"]
 pub struct ComponentBuilder(Vec<Component>);
 
+impl Default for ComponentBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ComponentBuilder {
     /// Create a new builder to construct a Vec<[`Component`]>.
     pub const fn new() -> Self {
@@ -45,6 +51,10 @@ impl ComponentBuilder {
         self.action_row(vec![Component::SelectMenu(select_menu)])
     }
 
+    pub fn text_input(self, text_input: TextInput) -> Self {
+        self.action_row(vec![Component::TextInput(text_input)])
+    }
+
     pub fn action_row(mut self, components: Vec<Component>) -> Self {
         if self.is_full() {
             return self;
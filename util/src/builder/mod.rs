@@ -1,9 +1,13 @@
 pub mod button;
 pub mod component;
+pub mod modal;
 pub mod select_menu;
 pub mod select_menu_option;
+pub mod text_input;
 
 pub use button::ButtonBuilder;
 pub use component::ComponentBuilder;
+pub use modal::ModalBuilder;
 pub use select_menu::SelectMenuBuilder;
 pub use select_menu_option::SelectMenuOptionBuilder;
+pub use text_input::{TextInputBuilder, MAX_TEXT_INPUT_VALUE_LEN};
@@ -0,0 +1,57 @@
+use twilight_model::{
+    application::component::{ActionRow, Component, TextInput},
+    http::interaction::{InteractionResponse, InteractionResponseData, InteractionResponseType},
+};
+
+pub const MAX_MODAL_COMPONENT_LEN: usize = 5;
+
+#[derive(Clone, Debug)]
+#[must_use = "builders have no effect if unused"]
+pub struct ModalBuilder {
+    custom_id: String,
+    title: String,
+    components: Vec<Component>,
+}
+
+impl ModalBuilder {
+    /// Create a new builder to construct a modal [`InteractionResponse`].
+    pub const fn new(custom_id: String, title: String) -> Self {
+        Self {
+            custom_id,
+            title,
+            components: Vec::new(),
+        }
+    }
+
+    /// Consume the builder, returning an [`InteractionResponse`] which opens the modal.
+    #[allow(clippy::missing_const_for_fn)]
+    #[must_use = "builders have no effect if unused"]
+    pub fn build(self) -> InteractionResponse {
+        InteractionResponse {
+            kind: InteractionResponseType::Modal,
+            data: Some(InteractionResponseData {
+                components: Some(self.components),
+                custom_id: Some(self.custom_id),
+                title: Some(self.title),
+                ..Default::default()
+            }),
+        }
+    }
+
+    pub fn text_input(mut self, text_input: TextInput) -> Self {
+        if self.is_full() {
+            return self;
+        }
+
+        // Every TextInput needs its own ActionRow
+        self.components.push(Component::ActionRow(ActionRow {
+            components: vec![Component::TextInput(text_input)],
+        }));
+
+        self
+    }
+
+    fn is_full(&self) -> bool {
+        self.components.len() == MAX_MODAL_COMPONENT_LEN
+    }
+}
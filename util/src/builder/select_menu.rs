@@ -43,7 +43,7 @@ impl SelectMenuBuilder {
     pub fn max_values(mut self, max_values: Option<u8>) -> Self {
         self.0.max_values = match max_values {
             Some(val) if val > 25 => Some(25),
-            Some(val) if val == 0 => None,
+            Some(0) => None,
             Some(_) => max_values,
             None => None,
         };
@@ -0,0 +1,71 @@
+use twilight_model::application::component::{text_input::TextInputStyle, TextInput};
+
+pub const MAX_TEXT_INPUT_VALUE_LEN: u16 = 4000;
+
+#[derive(Clone, Debug)]
+#[must_use = "builders have no effect if unused"]
+pub struct TextInputBuilder(TextInput);
+
+impl TextInputBuilder {
+    /// Create a new builder to construct a [`TextInput`].
+    pub const fn new(style: TextInputStyle, custom_id: String, label: String) -> Self {
+        Self(TextInput {
+            custom_id,
+            label,
+            max_length: None,
+            min_length: None,
+            placeholder: None,
+            required: None,
+            style,
+            value: None,
+        })
+    }
+
+    /// Consume the builder, returning a [`TextInput`].
+    #[allow(clippy::missing_const_for_fn)]
+    #[must_use = "builders have no effect if unused"]
+    pub fn build(self) -> TextInput {
+        self.0
+    }
+
+    #[allow(clippy::missing_const_for_fn)]
+    #[must_use = "builders have no effect if unused"]
+    pub fn max_length(mut self, max_length: Option<u16>) -> Self {
+        self.0.max_length = max_length.map(|val| val.min(MAX_TEXT_INPUT_VALUE_LEN));
+
+        self
+    }
+
+    #[allow(clippy::missing_const_for_fn)]
+    #[must_use = "builders have no effect if unused"]
+    pub fn min_length(mut self, min_length: Option<u16>) -> Self {
+        self.0.min_length = min_length.map(|val| val.min(MAX_TEXT_INPUT_VALUE_LEN));
+
+        self
+    }
+
+    #[allow(clippy::missing_const_for_fn)]
+    #[must_use = "builders have no effect if unused"]
+    pub fn placeholder(mut self, placeholder: Option<String>) -> Self {
+        self.0.placeholder = placeholder;
+
+        self
+    }
+
+    #[allow(clippy::missing_const_for_fn)]
+    #[must_use = "builders have no effect if unused"]
+    pub fn required(mut self, required: bool) -> Self {
+        self.0.required = Some(required);
+
+        self
+    }
+
+    /// Pre-fill the input, Discord rejects values longer than [`MAX_TEXT_INPUT_VALUE_LEN`].
+    #[allow(clippy::missing_const_for_fn)]
+    #[must_use = "builders have no effect if unused"]
+    pub fn value(mut self, value: String) -> Self {
+        self.0.value = Some(value);
+
+        self
+    }
+}